@@ -1,46 +1,284 @@
-use rand::thread_rng;
-use csv;
-mod neat;
+#![allow(clippy::needless_return)]
+
+use neat::neat;
+use neat::termination::StopCondition;
+use rand::SeedableRng;
 
 /*
 TODO: 
-- load custom dataset 
-- test dataset 
+- load custom dataset
 - verify structure
 - genome and specimen distinction
-- logs filtering and saving
 */
 
 
-fn read_data_frame(path:&str) -> neat::generations::DataFrame {
-    use std::fs::File;
+fn read_dataset(inputs_path:&str, outputs_path:&str, config:&neat::structs::config::Config) -> neat::generations::Dataset {
+    let dataset = neat::generations::Dataset::load(inputs_path, outputs_path)
+        .unwrap_or_else(|error| panic!("Couldn't read {:?} and {:?}: {}", inputs_path, outputs_path, error));
+    if config.log_level >= neat::statistics::LogLevel::Species {
+        println!("Reading {:?}", inputs_path);
+        dataset.inputs.iter().for_each(|row| println!("{:?}", row));
+        println!("Reading {:?}", outputs_path);
+        dataset.outputs.iter().for_each(|row| println!("{:?}", row));
+    }
+    return dataset;
+}
+
+fn parse_argument<T: std::str::FromStr>(args:&[String], index:usize, default:T) -> T {
+    return match args.get(index) {
+        Some(value) => value.parse().unwrap_or_else(|_| panic!("Invalid argument: {:?}", value)),
+        None => default
+    };
+}
 
-    println!("Reading {:?}", path);
+fn parse_option<T: std::str::FromStr>(value:&str) -> T {
+    return value.parse().unwrap_or_else(|_| panic!("Invalid option value: {:?}", value));
+}
 
-    let file = File::open(path).unwrap();
-    let mut rdr = csv::Reader::from_reader(file);
-    let mut result = Vec::new();
-    for record in rdr.records() {
-        let parsed = record
-            .unwrap()
-            .iter()
-            .map(|x| x.parse::<f64>().unwrap())
-            .collect::<Vec<_>>();
-        println!("{:?}", parsed);
-        result.push(parsed);
+// genome or ensemble, chosen by the file extension, with the problem it was trained on
+fn load_model(path:&str) -> (Box<dyn neat::evaluation::Predictor>, neat::structs::problem::Problem) {
+    if path.ends_with(".ensemble") {
+        let (ensemble, problem) = neat::ensemble::Ensemble::load(path).unwrap_or_else(|error| panic!("Couldn't load {:?}: {}", path, error));
+        return (Box::new(ensemble), problem);
     }
+    let (genome, problem) = neat::structs::genome::Genome::load(path).unwrap_or_else(|error| panic!("Couldn't load {:?}: {}", path, error));
+    return (Box::new(genome), problem);
+}
 
-    return result;
+// columns of the dataset have to match nodes of the saved model
+fn check_columns(problem:&neat::structs::problem::Problem, dataset:&neat::generations::Dataset) {
+    if problem.inputs != dataset.input_names.len() + 1 || problem.outputs != dataset.output_names.len() {
+        panic!("Model has {} inputs and {} outputs, dataset has {} input and {} output columns", 
+            problem.inputs - 1, problem.outputs, dataset.input_names.len(), dataset.output_names.len());
+    }
 }
 
-fn main() {
+// options applied only when their parent option enables the feature
+const TUNING_OPTIONS:[&str; 3] = ["--learning-rate=", "--backprop-elites", "--baldwinian"];
+const SEARCH_OPTIONS:[&str; 2] = ["--search-evaluations=", "--search-sigma="];
 
-    let mut rng = thread_rng();
+fn matches_any(option:&str, prefixes:&[&str]) -> bool {
+    return prefixes.iter().any(|prefix| option.starts_with(prefix));
+}
 
-    let dataset: neat::generations::Dataset = neat::generations::Dataset {
-        inputs: read_data_frame("inputs.csv"),
-        outputs: read_data_frame("outputs.csv")
-    };
+fn main() {
+    // options start with "--", remaining arguments are positional 
+    let (options, args):(Vec<String>, Vec<String>) = std::env::args().partition(|x| x.starts_with("--"));
+    let mut config = neat::structs::config::Config::default();
+    let show_disabled = options.iter().any(|x| x == "--show-disabled");
+    if options.iter().any(|x| x == "--regression") {
+        config.task = neat::metrics::Task::Regression;
+        config.output_activation = neat::evaluation::OutputActivation::Identity;
+    }
+    for option in options.iter() {
+        if option == "--quiet" {
+            config.log_level = neat::statistics::LogLevel::Silent;
+        } else if let Some(level) = option.strip_prefix("--log-level=") {
+            config.log_level = match level {
+                "silent" => neat::statistics::LogLevel::Silent,
+                "generations" => neat::statistics::LogLevel::Generations,
+                "species" => neat::statistics::LogLevel::Species,
+                _ => panic!("Invalid log level: {:?}", level)
+            };
+        } else if let Some(generations) = option.strip_prefix("--generations=") {
+            config.stop_conditions.retain(|condition| !matches!(condition, StopCondition::MaxGenerations(_)));
+            config.stop_conditions.push(StopCondition::MaxGenerations(parse_option(generations)));
+        } else if let Some(fitness) = option.strip_prefix("--target-fitness=") {
+            config.stop_conditions.push(StopCondition::FitnessThreshold { fitness: parse_option(fitness), validation: true });
+        } else if let Some(seconds) = option.strip_prefix("--time-budget=") {
+            config.stop_conditions.push(StopCondition::TimeBudget(std::time::Duration::from_secs_f64(parse_option(seconds))));
+        } else if let Some(evaluations) = option.strip_prefix("--max-evaluations=") {
+            config.stop_conditions.push(StopCondition::MaxEvaluations(parse_option(evaluations)));
+        } else if let Some(generations) = option.strip_prefix("--stagnation=") {
+            config.stop_conditions.push(StopCondition::Stagnation(parse_option(generations)));
+        } else if let Some(size) = option.strip_prefix("--batch-size=") {
+            config.batch_size = Some(parse_option(size));
+        } else if let Some(part) = option.strip_prefix("--validation=") {
+            config.validation_part = parse_option(part);
+        } else if let Some(part) = option.strip_prefix("--test=") {
+            config.test_part = parse_option(part);
+        } else if let Some(sigma) = option.strip_prefix("--weight-init=") {
+            config.weight_init = neat::genetics::WeightDistribution::Gaussian { mean: 0.0, sigma: parse_option(sigma) };
+        } else if let Some(sigma) = option.strip_prefix("--weight-sigma=") {
+            config.weight_perturbation = neat::genetics::WeightPerturbation::Gaussian(parse_option(sigma));
+        } else if let Some(chance) = option.strip_prefix("--weight-replace=") {
+            config.weight_replace_chance = parse_option(chance);
+        } else if let Some(limit) = option.strip_prefix("--weight-limit=") {
+            let limit:f64 = parse_option(limit);
+            if limit.is_nan() || limit < 0f64 {
+                panic!("Weight limit can't be negative: {:?}", option);
+            }
+            config.min_weight = -limit;
+            config.max_weight = limit;
+        } else if option == "--self-adaptive" {
+            config.self_adaptive_mutation = true;
+        } else if let Some(epochs) = option.strip_prefix("--backprop=") {
+            config.fine_tuning = Some(neat::backpropagation::FineTuning {
+                epochs: parse_option(epochs),
+                learning_rate: 0.01,
+                elites: false,
+                champion: true,
+                inheritance: neat::backpropagation::Inheritance::Lamarckian
+            });
+        } else if let Some(optimiser) = option.strip_prefix("--weight-search=") {
+            config.weight_search = Some(neat::weight_search::WeightSearch {
+                optimiser: parse_option(optimiser),
+                evaluations: 1000,
+                sigma: 0.5
+            });
+        } else if let Some(selection) = option.strip_prefix("--selection=") {
+            config.selection = parse_option(selection);
+        } else if let Some(part) = option.strip_prefix("--mutation-only=") {
+            config.mutation_only_part = parse_option(part);
+        } else if let Some(chance) = option.strip_prefix("--interspecies=") {
+            config.interspecies_mating_chance = parse_option(chance);
+        } else if let Some(chance) = option.strip_prefix("--delete-connection=") {
+            config.delete_connection_chance = parse_option(chance);
+        } else if let Some(chance) = option.strip_prefix("--delete-node=") {
+            config.delete_node_chance = parse_option(chance);
+        } else if let Some(chance) = option.strip_prefix("--reenable=") {
+            config.reenable_chance = parse_option(chance);
+        } else if let Some(generations) = option.strip_prefix("--disabled-lifetime=") {
+            config.disabled_gene_lifetime = Some(parse_option(generations));
+        } else if let Some(strategy) = option.strip_prefix("--restart=") {
+            config.restart = parse_option(strategy);
+        } else if let Some(size) = option.strip_prefix("--ensemble=") {
+            config.ensemble_size = Some(parse_option(size));
+        } else if let Some(combination) = option.strip_prefix("--combination=") {
+            config.ensemble_combination = parse_option(combination);
+        } else if let Some(seed) = option.strip_prefix("--seed=") {
+            config.seed = Some(parse_option(seed));
+        } else if let Some(path) = option.strip_prefix("--statistics=") {
+            // format is chosen by the file extension
+            config.statistics_format = if path.ends_with(".jsonl") {
+                neat::statistics::StatisticsFormat::JsonLines
+            } else {
+                neat::statistics::StatisticsFormat::Csv
+            };
+            config.statistics_path = Some(path.to_string());
+        } else if option == "--show-disabled" || option == "--regression" || option == "--softmax"
+            || matches_any(option, &TUNING_OPTIONS) || matches_any(option, &SEARCH_OPTIONS) {
+            // handled before or after this loop
+        } else {
+            panic!("Unknown option: {:?}", option);
+        }
+    }
+    for option in options.iter() {
+        if config.fine_tuning.is_none() && matches_any(option, &TUNING_OPTIONS) {
+            panic!("Option {:?} requires --backprop=<epochs>", option);
+        }
+        if config.weight_search.is_none() && matches_any(option, &SEARCH_OPTIONS) {
+            panic!("Option {:?} requires --weight-search=<optimiser>", option);
+        }
+    }
+    // options changing fine-tuning are applied after it's enabled
+    if let Some(tuning) = config.fine_tuning.as_mut() {
+        for option in options.iter() {
+            if let Some(rate) = option.strip_prefix("--learning-rate=") {
+                tuning.learning_rate = parse_option(rate);
+            } else if option == "--backprop-elites" {
+                tuning.elites = true;
+            } else if option == "--baldwinian" {
+                tuning.inheritance = neat::backpropagation::Inheritance::Baldwinian;
+            }
+        }
+    }
+    if let Some(search) = config.weight_search.as_mut() {
+        for option in options.iter() {
+            if let Some(evaluations) = option.strip_prefix("--search-evaluations=") {
+                search.evaluations = parse_option(evaluations);
+            } else if let Some(sigma) = option.strip_prefix("--search-sigma=") {
+                search.sigma = parse_option(sigma);
+            }
+        }
+    }
+    if options.iter().any(|x| x == "--softmax") {
+        config.output_activation = neat::evaluation::OutputActivation::Softmax;
+        config.fitness_function = neat::evaluation::FitnessFunction::CrossEntropy;
+    }
+    config.validate().unwrap_or_else(|error| panic!("Invalid config: {}", error));
 
-    neat::generations::run(&mut rng, &dataset, &dataset);
+    match args.get(1).map(|x| x.as_str()) {
+        // cross-validate [folds] [seed]
+        Some("cross-validate") => {
+            let dataset = read_dataset("inputs.csv", "outputs.csv", &config);
+            let folds = parse_argument(&args, 2, 5usize);
+            let seed = parse_argument(&args, 3, 0u64);
+            let report = neat::cross_validation::cross_validate(&config, &dataset, folds, seed);
+            report.print_summary();
+            report.save("cross_validation.csv").unwrap();
+            report.save_genomes(".").unwrap();
+        }
+        // evaluate [model] [inputs] [outputs]
+        Some("evaluate") => {
+            let model_path = parse_argument(&args, 2, "best.genome".to_string());
+            let inputs_path = parse_argument(&args, 3, "inputs.csv".to_string());
+            let outputs_path = parse_argument(&args, 4, "outputs.csv".to_string());
+            let dataset = read_dataset(&inputs_path, &outputs_path, &config);
+            let (mut model, problem) = load_model(&model_path);
+            check_columns(&problem, &dataset);
+            // models with unbounded or scaled outputs are trained for regression
+            let task = if problem.output_activation.is_bounded() { config.task } else { neat::metrics::Task::Regression };
+            let report = neat::metrics::report(task, &problem, model.as_mut(), &dataset);
+            print!("{}", report);
+        }
+        // predict [model] [inputs] [predictions] [outputs], 
+        // outputs file gives only names and number of output columns
+        Some("predict") => {
+            let model_path = parse_argument(&args, 2, "best.genome".to_string());
+            let inputs_path = parse_argument(&args, 3, "inputs.csv".to_string());
+            let predictions_path = parse_argument(&args, 4, "predictions.csv".to_string());
+            let outputs_path = parse_argument(&args, 5, "outputs.csv".to_string());
+            let dataset = read_dataset(&inputs_path, &outputs_path, &config);
+            let (mut model, problem) = load_model(&model_path);
+            check_columns(&problem, &dataset);
+            let mut writer = csv::Writer::from_path(&predictions_path).unwrap();
+            writer.write_record(&dataset.output_names).unwrap();
+            for input in dataset.inputs.iter() {
+                let prediction = model.predict(input, &problem);
+                writer.write_record(prediction.iter().map(|x| x.to_string())).unwrap();
+            }
+            writer.flush().unwrap();
+        }
+        // dot [genome] [output]
+        Some("dot") => {
+            let genome_path = parse_argument(&args, 2, "best.genome".to_string());
+            let output_path = parse_argument(&args, 3, "out.dot".to_string());
+            let dataset = read_dataset("inputs.csv", "outputs.csv", &config);
+            let (genome, problem) = neat::structs::genome::Genome::load(&genome_path).unwrap();
+            check_columns(&problem, &dataset);
+            neat::visualisation::write_dot(&genome, &problem, &dataset, show_disabled, &output_path).unwrap();
+        }
+        _ => {
+            let dataset = read_dataset("inputs.csv", "outputs.csv", &config);
+            let mut random = match config.seed {
+                Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
+                None => rand::rngs::StdRng::from_entropy()
+            };
+            let (training, validation, test) = dataset.split(&mut random, config.validation_part, config.test_part);
+            let reporters = neat::reporting::default_reporters(&config);
+            let result = neat::generations::run(&config, &training, &validation, reporters);
+            let mut best = result.best;
+            let problem = dataset.problem(&config);
+            best.save(&problem, "best.genome").unwrap();
+            // test rows took no part in the run so their score is unbiased 
+            if !test.is_empty() && config.log_level >= neat::statistics::LogLevel::Generations {
+                println!("test:");
+                print!("{}", neat::metrics::report(config.task, &problem, &mut best, &test));
+            }
+            if let Some(mut ensemble) = result.ensemble {
+                ensemble.save(&problem, "best.ensemble").unwrap();
+                if !test.is_empty() && config.log_level >= neat::statistics::LogLevel::Generations {
+                    println!("ensemble of {} genomes, test:", ensemble.members.len());
+                    print!("{}", neat::metrics::report(config.task, &problem, &mut ensemble, &test));
+                }
+            }
+            if let Err(error) = neat::history::write_report(&result.history, "progress.html") {
+                println!("Couldn't write the progress report: {}", error);
+            }
+            if let Err(error) = neat::visualisation::visualise(&best, &problem, show_disabled, "out.html") {
+                println!("Couldn't write the visualisation: {}", error);
+            }
+        }
+    }
 }
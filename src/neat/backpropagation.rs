@@ -0,0 +1,239 @@
+use super::structs::genome::Genome;
+use super::structs::problem::Problem;
+use super::structs::config::Config;
+use super::evaluation::{cross_entropy_gradient, network_input, sigmoid, FitnessFunction, OutputActivation};
+use super::generations::Dataset;
+
+// how weights tuned by backpropagation are used
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum Inheritance {
+    // tuned weights replace weights of the genome and are inherited by its offspring
+    Lamarckian,
+    // genome keeps its weights, tuned ones only give it fitness
+    Baldwinian
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct FineTuning {
+    pub epochs: usize,
+    pub learning_rate: f64,
+    // tune the best specimen of each specie in every generation
+    pub elites: bool,
+    // tune the best genome at the end of the run, tuned weights are kept
+    // only if validation fitness doesn't get worse
+    pub champion: bool,
+    // applies only to elites, champion is compared by validation fitness instead
+    pub inheritance: Inheritance
+}
+
+// enabled genes of a genome in the order of evaluation,
+// node is evaluated when all nodes connected to it are, like in `evaluation::evaluate`
+struct Network {
+    // all nodes, each after nodes connected to it
+    order: Vec<usize>,
+    // indices of enabled genes leading to the node from evaluated nodes
+    incoming: Vec<Vec<usize>>
+}
+
+impl Network {
+    // None if enabled genes contain a cycle
+    fn compile(genome:&Genome, problem:&Problem) -> Option<Network> {
+        let mut incoming_count = vec![0usize; genome.nodes];
+        let mut outgoing = vec![Vec::new(); genome.nodes];
+        for (index, gene) in genome.genes.iter().enumerate().filter(|(_, gene)| gene.enabled) {
+            incoming_count[gene.output] += 1;
+            outgoing[gene.input].push(index);
+        }
+
+        let mut evaluated:Vec<bool> = (0..genome.nodes).map(|node| node < problem.inputs).collect();
+        let mut waiting = incoming_count.clone();
+        let mut order:Vec<usize> = (0..genome.nodes).filter(|&node| waiting[node] == 0).collect();
+        let mut next = 0;
+        while next < order.len() {
+            let node = order[next];
+            next += 1;
+            for &gene in outgoing[node].iter() {
+                let output = genome.genes[gene].output;
+                waiting[output] -= 1;
+                if waiting[output] == 0 {
+                    order.push(output);
+                }
+            }
+        }
+        if order.len() < genome.nodes {
+            return None;
+        }
+
+        let mut incoming = vec![Vec::new(); genome.nodes];
+        for &node in order.iter() {
+            if node >= problem.inputs && incoming_count[node] > 0 {
+                evaluated[node] = genome.genes.iter().all(|gene| !gene.enabled || gene.output != node || evaluated[gene.input]);
+            }
+        }
+        for (index, gene) in genome.genes.iter().enumerate() {
+            if gene.enabled && evaluated[gene.input] {
+                incoming[gene.output].push(index);
+            }
+        }
+        return Some(Network { order, incoming });
+    }
+
+    // value passed on by the node, input nodes pass their values unchanged
+    fn activation(problem:&Problem, node:usize, value:f64) -> f64 {
+        if node < problem.inputs {
+            return value;
+        }
+        return sigmoid(value);
+    }
+
+    // values of nodes before activation, input includes bias
+    fn forward(&self, genome:&Genome, problem:&Problem, input:&[f64]) -> Vec<f64> {
+        let mut values = vec![0f64; genome.nodes];
+        values[..input.len()].copy_from_slice(input);
+        for &node in self.order.iter().filter(|&&node| node >= problem.inputs) {
+            values[node] = self.incoming[node].iter().map(|&gene| {
+                let gene = &genome.genes[gene];
+                return gene.weight * Network::activation(problem, gene.input, values[gene.input]);
+            }).sum();
+        }
+        return values;
+    }
+}
+
+// gradient of the error of a single row measured by the fitness function
+// with respect to activated outputs
+fn error_gradient(problem:&Problem, outputs:&[f64], expected:&[f64]) -> Vec<f64> {
+    return match problem.fitness_function {
+        FitnessFunction::MeanAbsoluteError => outputs.iter().zip(expected)
+            .map(|(output, target)| (output - target).signum() / outputs.len() as f64).collect(),
+        FitnessFunction::CrossEntropy =>
+            cross_entropy_gradient(outputs, expected, matches!(problem.output_activation, OutputActivation::Softmax))
+    };
+}
+
+// trains enabled weights with stochastic gradient descent, weights are updated after every row,
+// returns false if the genome has cycles and can't be trained
+pub fn train(genome:&mut Genome, problem:&Problem, config:&Config, dataset:&Dataset, epochs:usize, learning_rate:f64) -> bool {
+    let network = match Network::compile(genome, problem) {
+        Some(network) => network,
+        None => return false
+    };
+    let output_nodes = problem.inputs..problem.inputs + problem.outputs;
+
+    for _ in 0..epochs {
+        for (row, expected) in dataset.inputs.iter().zip(dataset.outputs.iter()) {
+            let values = network.forward(genome, problem, &network_input(row));
+            let outputs = problem.output_activation.apply(&values[output_nodes.clone()]);
+            let output_gradient = problem.output_activation.backward(&outputs, &error_gradient(problem, &outputs, expected));
+
+            let mut gradient = vec![0f64; genome.nodes];
+            gradient[output_nodes.clone()].copy_from_slice(&output_gradient);
+            // nodes are visited after all nodes they lead to, so their gradient is complete
+            for &node in network.order.iter().rev() {
+                if gradient[node] == 0f64 {
+                    continue;
+                }
+                for &gene in network.incoming[node].iter() {
+                    let input_node = genome.genes[gene].input;
+                    let activation = Network::activation(problem, input_node, values[input_node]);
+                    if input_node >= problem.inputs {
+                        let derivative = activation * (1f64 - activation);
+                        gradient[input_node] += gradient[node] * genome.genes[gene].weight * derivative;
+                    }
+                    let weight = genome.genes[gene].weight - learning_rate * gradient[node] * activation;
+                    genome.genes[gene].weight = weight.clamp(config.min_weight, config.max_weight);
+                }
+            }
+        }
+    }
+    return true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::evaluation::{cross_entropy, predict};
+
+    // hidden node 5, output to output connection 3 -> 4 and a disabled gene 2 -> 4
+    const GENOME:&str = "nodes 6
+0 3 0.5 true 0
+1 3 -0.4 true 0
+1 5 0.8 true 1
+2 5 -0.6 true 1
+5 3 0.7 true 1
+5 4 -0.3 true 2
+0 4 0.2 true 0
+3 4 0.9 true 2
+2 4 0.4 false 0
+";
+
+    fn problem(output_activation:OutputActivation, fitness_function:FitnessFunction) -> Problem {
+        return Problem { inputs: 3, outputs: 2, output_activation, fitness_function };
+    }
+
+    fn dataset() -> Dataset {
+        return Dataset {
+            inputs: vec![vec![0.3, -0.7]],
+            outputs: vec![vec![1.0, 0.0]],
+            input_names: vec!["a".to_string(), "b".to_string()],
+            output_names: vec!["x".to_string(), "y".to_string()]
+        };
+    }
+
+    // error of the only row of the dataset minimised by `train`
+    fn error(problem:&Problem, genome:&Genome, dataset:&Dataset) -> f64 {
+        let outputs = predict(&dataset.inputs[0], problem, &mut genome.clone());
+        let expected = &dataset.outputs[0];
+        return match problem.fitness_function {
+            FitnessFunction::MeanAbsoluteError => 
+                outputs.iter().zip(expected).map(|(output, target)| (output - target).abs()).sum::<f64>() / outputs.len() as f64,
+            FitnessFunction::CrossEntropy => 
+                cross_entropy(&outputs, expected, matches!(problem.output_activation, OutputActivation::Softmax))
+        };
+    }
+
+    // change of every weight after a single step of `train` is compared
+    // with the gradient calculated by central differences
+    fn check_gradient(problem:&Problem) {
+        const LEARNING_RATE:f64 = 0.1;
+        const STEP:f64 = 1e-6;
+        let genome:Genome = GENOME.parse().unwrap();
+        let dataset = dataset();
+        let mut trained = genome.clone();
+        assert!(train(&mut trained, problem, &Config::default(), &dataset, 1, LEARNING_RATE));
+
+        for (index, gene) in genome.genes.iter().enumerate() {
+            let mut plus = genome.clone();
+            plus.genes[index].weight += STEP;
+            let mut minus = genome.clone();
+            minus.genes[index].weight -= STEP;
+            let numerical = (error(problem, &plus, &dataset) - error(problem, &minus, &dataset)) / (2f64 * STEP);
+            let analytical = (gene.weight - trained.genes[index].weight) / LEARNING_RATE;
+            assert!((numerical - analytical).abs() < 1e-6, 
+                "gene {} -> {}: numerical {} != analytical {}", gene.input, gene.output, numerical, analytical);
+        }
+    }
+
+    #[test]
+    fn softmax_cross_entropy_gradient() {
+        check_gradient(&problem(OutputActivation::Softmax, FitnessFunction::CrossEntropy));
+    }
+
+    #[test]
+    fn sigmoid_cross_entropy_gradient() {
+        check_gradient(&problem(OutputActivation::Sigmoid, FitnessFunction::CrossEntropy));
+    }
+
+    #[test]
+    fn scaled_mean_absolute_error_gradient() {
+        check_gradient(&problem(OutputActivation::Scaled { min: -2.0, max: 3.0 }, FitnessFunction::MeanAbsoluteError));
+    }
+
+    #[test]
+    fn identity_mean_absolute_error_gradient() {
+        check_gradient(&problem(OutputActivation::Identity, FitnessFunction::MeanAbsoluteError));
+    }
+}
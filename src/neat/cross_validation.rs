@@ -0,0 +1,154 @@
+use super::structs::genome::Genome;
+use super::structs::problem::Problem;
+use super::generations::{run, calculate_base_fitness, Dataset};
+use super::structs::config::Config;
+use super::statistics::LogLevel;
+use super::reporting::{Reporter, StdoutReporter};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::error::Error;
+use std::path::Path;
+
+#[derive(Debug)]
+pub struct Fold {
+    pub index: usize,
+    pub seed: u64,
+    pub best: Genome,
+    // fitness of the best genome on the held-out fold, which took no part in the run
+    pub test_fitness: f64
+}
+
+#[derive(Debug)]
+pub struct CrossValidationReport {
+    // problem genomes of all folds are trained on, it's saved with them
+    pub problem: Problem,
+    pub folds: Vec<Fold>
+}
+
+type Metric = (&'static str, fn(&Fold) -> f64);
+
+// metrics of the best genome reported for every fold, 
+// used both in the summary table and the csv file
+const METRICS: [Metric; 5] = [
+    ("training_fitness", |fold| fold.best.fitness),
+    ("validation_fitness", |fold| fold.best.validation_fitness),
+    ("test_fitness", |fold| fold.test_fitness),
+    ("nodes", |fold| fold.best.active_nodes as f64),
+    ("genes", |fold| fold.best.active_genes() as f64),
+];
+
+fn mean_and_std(values:&[f64]) -> (f64, f64) {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    if values.len() < 2 {
+        return (mean, 0f64);
+    }
+    // sample standard deviation, folds are a sample of possible splits
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    return (mean, variance.sqrt());
+}
+
+impl CrossValidationReport {
+    pub fn metric(&self, name:&str) -> (f64, f64) {
+        let (_, getter) = METRICS.iter().find(|(metric, _)| *metric == name).unwrap();
+        let values:Vec<f64> = self.folds.iter().map(getter).collect();
+        return mean_and_std(&values);
+    }
+
+    pub fn print_summary(&self) {
+        println!("fold\tseed\ttraining\tvalidation\ttest\t\tnodes\tgenes");
+        for fold in self.folds.iter() {
+            println!("{}\t{}\t{:.5}\t\t{:.5}\t\t{:.5}\t\t{}\t{}",
+                fold.index, fold.seed, fold.best.fitness, fold.best.validation_fitness, fold.test_fitness,
+                fold.best.active_nodes, fold.best.active_genes());
+        }
+        let summary:Vec<(f64, f64)> = METRICS.iter().map(|(name, _)| self.metric(name)).collect();
+        println!("mean\t\t{:.5}\t\t{:.5}\t\t{:.5}\t\t{:.2}\t{:.2}",
+            summary[0].0, summary[1].0, summary[2].0, summary[3].0, summary[4].0);
+        println!("std\t\t{:.5}\t\t{:.5}\t\t{:.5}\t\t{:.2}\t{:.2}",
+            summary[0].1, summary[1].1, summary[2].1, summary[3].1, summary[4].1);
+    }
+
+    // writes the best genome of every fold to `fold_<index>.genome` in the given directory
+    pub fn save_genomes<P: AsRef<Path>>(&self, directory:P) -> std::io::Result<()> {
+        for fold in self.folds.iter() {
+            fold.best.save(&self.problem, directory.as_ref().join(format!("fold_{}.genome", fold.index)))?;
+        }
+        return Ok(());
+    }
+
+    // writes one row per fold followed by mean and std rows
+    pub fn save<P: AsRef<Path>>(&self, path:P) -> Result<(), Box<dyn Error>> {
+        let mut writer = csv::Writer::from_path(path)?;
+        let mut header = vec!["fold".to_string(), "seed".to_string()];
+        header.extend(METRICS.iter().map(|(name, _)| name.to_string()));
+        writer.write_record(&header)?;
+
+        for fold in self.folds.iter() {
+            let mut record = vec![fold.index.to_string(), fold.seed.to_string()];
+            record.extend(METRICS.iter().map(|(_, getter)| getter(fold).to_string()));
+            writer.write_record(&record)?;
+        }
+
+        let summary:Vec<(f64, f64)> = METRICS.iter().map(|(name, _)| self.metric(name)).collect();
+        let mut mean_record = vec!["mean".to_string(), String::new()];
+        mean_record.extend(summary.iter().map(|(mean, _)| mean.to_string()));
+        writer.write_record(&mean_record)?;
+        let mut std_record = vec!["std".to_string(), String::new()];
+        std_record.extend(summary.iter().map(|(_, std)| std.to_string()));
+        writer.write_record(&std_record)?;
+
+        writer.flush()?;
+        return Ok(());
+    }
+}
+
+// splits shuffled row indices into `folds` parts of nearly equal size
+fn split_folds(dataset:&Dataset, folds:usize, seed:u64) -> Vec<Vec<usize>> {
+    let mut indices:Vec<usize> = (0..dataset.len()).collect();
+    // datasets are often sorted by class so rows have to be shuffled before splitting
+    indices.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let mut result = vec![Vec::new(); folds];
+    for (position, index) in indices.into_iter().enumerate() {
+        result[position % folds].push(index);
+    }
+    return result;
+}
+
+pub fn cross_validate(config:&Config, dataset:&Dataset, folds:usize, seed:u64) -> CrossValidationReport {
+    assert!(folds >= 2, "cross validation needs at least two folds");
+    assert!(dataset.len() >= folds, "dataset has fewer rows than folds");
+
+    let split = split_folds(dataset, folds, seed);
+    let mut report = CrossValidationReport { problem: dataset.problem(config), folds: Vec::new() };
+
+    for (index, test_indices) in split.iter().enumerate() {
+        let training_indices:Vec<usize> = split.iter().enumerate()
+            .filter(|(other, _)| *other != index)
+            .flat_map(|(_, indices)| indices.iter().copied())
+            .collect();
+        let fold_seed = seed.wrapping_add(index as u64 + 1);
+        // champion is chosen on rows split from the training folds, 
+        // so the held-out fold gives an unbiased score
+        let (training_dataset, validation_dataset, _) = dataset.subset(&training_indices)
+            .split(&mut StdRng::seed_from_u64(fold_seed), config.validation_part, 0f64);
+        let test_dataset = dataset.subset(test_indices);
+
+        if config.log_level >= LogLevel::Generations {
+            println!("[fold {}/{}]\tseed: {}, \ttraining rows: {}, \tvalidation rows: {}, \ttest rows: {}",
+                index + 1, folds, fold_seed, training_dataset.len(), validation_dataset.len(), test_dataset.len());
+        }
+
+        let fold_config = Config { seed: Some(fold_seed), ..config.clone() };
+        // statistics file would be overwritten by each fold so only stdout is used
+        let reporters:Vec<Box<dyn Reporter>> = vec![Box::new(StdoutReporter::new(config.log_level))];
+        let mut best = run(&fold_config, &training_dataset, &validation_dataset, reporters).best;
+        // scoring doesn't change the fitness fields of the genome
+        let test_fitness = calculate_base_fitness(&report.problem, &mut best, &test_dataset);
+
+        report.folds.push(Fold { index, seed: fold_seed, best, test_fitness });
+    }
+
+    return report;
+}
@@ -0,0 +1,194 @@
+use super::structs::genome::Genome;
+use super::structs::problem::{load_model, save_model, Problem};
+use super::evaluation::{predict, Predictor};
+use super::generations::{base_fitness, Dataset};
+use super::metrics::class_of;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+// rounds of the greedy selection used to learn weights,
+// weight of a member is a multiple of 1 / WEIGHT_ROUNDS
+const WEIGHT_ROUNDS:usize = 20;
+
+// how predictions of members are combined
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum Combination {
+    Average,
+    // each member votes for the output with the highest value, a single output
+    // is treated as a binary class with 0.5 threshold, result is the fraction of votes
+    MajorityVote,
+    // weighted average with weights learned on the validation dataset
+    Weighted
+}
+
+impl fmt::Display for Combination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", match self {
+            Combination::Average => "average",
+            Combination::MajorityVote => "vote",
+            Combination::Weighted => "weighted"
+        });
+    }
+}
+
+impl FromStr for Combination {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        return match text {
+            "average" => Ok(Combination::Average),
+            "vote" => Ok(Combination::MajorityVote),
+            "weighted" => Ok(Combination::Weighted),
+            _ => Err(format!("unknown combination: {:?}", text))
+        };
+    }
+}
+
+// champions of several species predicting together
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Ensemble {
+    pub combination: Combination,
+    pub members: Vec<Genome>,
+    // sum up to one, equal unless the combination is Weighted
+    pub weights: Vec<f64>
+}
+
+fn weighted_average(predictions:&[Vec<f64>], weights:&[f64]) -> Vec<f64> {
+    let mut result = vec![0f64; predictions[0].len()];
+    for (prediction, weight) in predictions.iter().zip(weights) {
+        for (value, predicted) in result.iter_mut().zip(prediction) {
+            *value += weight * predicted;
+        }
+    }
+    return result;
+}
+
+impl Ensemble {
+    // members should be evaluated on the validation dataset before,
+    // it's used only to learn weights of the Weighted combination
+    pub fn new(members:Vec<Genome>, combination:Combination, problem:&Problem, validation_dataset:&Dataset) -> Ensemble {
+        let weights = match combination {
+            Combination::Weighted => learn_weights(&members, problem, validation_dataset),
+            _ => vec![1f64 / members.len() as f64; members.len()]
+        };
+        return Ensemble { combination, members, weights };
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, problem:&Problem, path:P) -> std::io::Result<()> {
+        return save_model(self, problem, path);
+    }
+
+    pub fn load<P: AsRef<Path>>(path:P) -> Result<(Ensemble, Problem), Box<dyn Error>> {
+        return load_model(path);
+    }
+}
+
+// greedy forward selection with replacement: in every round the member that improves
+// validation fitness of the average the most is added to it again
+fn learn_weights(members:&[Genome], problem:&Problem, validation_dataset:&Dataset) -> Vec<f64> {
+    // predictions of every member for every row are calculated once
+    let predictions:Vec<Vec<Vec<f64>>> = members.iter().map(|member| {
+        let mut member = member.clone();
+        return validation_dataset.inputs.iter().map(|input| predict(input, problem, &mut member)).collect();
+    }).collect();
+
+    let mut counts = vec![0usize; members.len()];
+    let mut sum = vec![vec![0f64; problem.outputs]; validation_dataset.len()];
+    for round in 1..=WEIGHT_ROUNDS {
+        let mut best_member = 0;
+        let mut best_fitness = f64::NEG_INFINITY;
+        for (member, member_predictions) in predictions.iter().enumerate() {
+            let average:Vec<Vec<f64>> = sum.iter().zip(member_predictions).map(|(row, predicted)| {
+                row.iter().zip(predicted).map(|(a, b)| (a + b) / round as f64).collect()
+            }).collect();
+            let fitness = base_fitness(problem, &average, validation_dataset);
+            if fitness > best_fitness {
+                best_fitness = fitness;
+                best_member = member;
+            }
+        }
+        counts[best_member] += 1;
+        for (row, predicted) in sum.iter_mut().zip(&predictions[best_member]) {
+            for (value, x) in row.iter_mut().zip(predicted) {
+                *value += x;
+            }
+        }
+    }
+    return counts.iter().map(|&count| count as f64 / WEIGHT_ROUNDS as f64).collect();
+}
+
+impl Predictor for Ensemble {
+    fn predict(&mut self, input:&[f64], problem:&Problem) -> Vec<f64> {
+        let predictions:Vec<Vec<f64>> = self.members.iter_mut().map(|member| predict(input, problem, member)).collect();
+        if let Combination::MajorityVote = self.combination {
+            let classes = if problem.outputs == 1 { 2 } else { problem.outputs };
+            let mut votes = vec![0f64; classes];
+            for (prediction, weight) in predictions.iter().zip(self.weights.iter()) {
+                votes[class_of(prediction)] += weight;
+            }
+            if problem.outputs == 1 {
+                // fraction of votes for the positive class
+                return vec![votes[1]];
+            }
+            return votes;
+        }
+        return weighted_average(&predictions, &self.weights);
+    }
+}
+
+// text format: "ensemble <combination>" line followed by "member <weight>" line
+// and genome in its text format for every member
+impl fmt::Display for Ensemble {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ensemble {}", self.combination)?;
+        for (member, weight) in self.members.iter().zip(self.weights.iter()) {
+            writeln!(f, "member {}", weight)?;
+            write!(f, "{}", member)?;
+        }
+        return Ok(());
+    }
+}
+
+impl FromStr for Ensemble {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let combination = match lines.next().map(|line| line.split_whitespace().collect::<Vec<_>>()) {
+            Some(header) if header.len() == 2 && header[0] == "ensemble" => header[1].parse::<Combination>()?,
+            _ => return Err("expected \"ensemble <combination>\" header".to_string())
+        };
+
+        // lines of each member are collected until the next "member" line
+        let mut members_text:Vec<(f64, String)> = Vec::new();
+        for line in lines {
+            let fields:Vec<&str> = line.split_whitespace().collect();
+            if fields.len() == 2 && fields[0] == "member" {
+                let weight = fields[1].parse().map_err(|_| format!("invalid member weight: {:?}", line))?;
+                members_text.push((weight, String::new()));
+            } else if let Some((_, member_text)) = members_text.last_mut() {
+                member_text.push_str(line);
+                member_text.push('\n');
+            } else {
+                return Err(format!("expected \"member <weight>\" line: {:?}", line));
+            }
+        }
+        if members_text.is_empty() {
+            return Err("ensemble has no members".to_string());
+        }
+
+        let mut members = Vec::new();
+        let mut weights = Vec::new();
+        for (weight, member_text) in members_text {
+            members.push(member_text.parse::<Genome>()?);
+            weights.push(weight);
+        }
+        return Ok(Ensemble { combination, members, weights });
+    }
+}
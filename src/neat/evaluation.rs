@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 use std::f64::consts::E;
+use std::fmt;
+use std::str::FromStr;
 use super::structs::problem::Problem;
 use super::structs::genome::Genome;
 
@@ -14,10 +16,153 @@ struct Node {
     is_input: bool,
 }
 
-fn sigmoid(value: f64) -> f64 {
+pub fn sigmoid(value: f64) -> f64 {
     return 1f64 / (1f64 + E.powf(-value));
 }
 
+// function applied to values of output nodes 
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum OutputActivation {
+    // outputs in range (0, 1), suitable for classification
+    Sigmoid,
+    // linear outputs for regression of unbounded values
+    Identity,
+    // sigmoid stretched to range (min, max) for regression of bounded values
+    Scaled { min: f64, max: f64 },
+    // outputs sum up to one and can be treated as class probabilities
+    Softmax
+}
+
+impl OutputActivation {
+    pub fn apply(&self, values: &[f64]) -> Vec<f64> {
+        return match self {
+            OutputActivation::Sigmoid => values.iter().map(|x| sigmoid(*x)).collect(),
+            OutputActivation::Identity => values.to_vec(),
+            OutputActivation::Scaled { min, max } => values.iter().map(|x| min + (max - min) * sigmoid(*x)).collect(),
+            OutputActivation::Softmax => {
+                // subtracting the maximum doesn't change the result but prevents overflow
+                let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let exponents:Vec<f64> = values.iter().map(|x| (x - max).exp()).collect();
+                let sum:f64 = exponents.iter().sum();
+                exponents.iter().map(|x| x / sum).collect()
+            }
+        };
+    }
+
+    // gradient with respect to values before activation given activated outputs 
+    // and gradient with respect to them
+    pub fn backward(&self, outputs: &[f64], gradient: &[f64]) -> Vec<f64> {
+        return match self {
+            OutputActivation::Sigmoid => outputs.iter().zip(gradient).map(|(y, g)| g * y * (1f64 - y)).collect(),
+            OutputActivation::Identity => gradient.to_vec(),
+            OutputActivation::Scaled { min, max } => outputs.iter().zip(gradient).map(|(y, g)| {
+                let s = (y - min) / (max - min);
+                g * (max - min) * s * (1f64 - s)
+            }).collect(),
+            OutputActivation::Softmax => {
+                let weighted:f64 = outputs.iter().zip(gradient).map(|(y, g)| y * g).sum();
+                outputs.iter().zip(gradient).map(|(y, g)| y * (g - weighted)).collect()
+            }
+        };
+    }
+
+    // whether errors of outputs are guaranteed to be within [0, 1] for targets in [0, 1]
+    pub fn is_bounded(&self) -> bool {
+        return matches!(self, OutputActivation::Sigmoid | OutputActivation::Softmax);
+    }
+}
+
+impl fmt::Display for OutputActivation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            OutputActivation::Sigmoid => write!(f, "sigmoid"),
+            OutputActivation::Identity => write!(f, "identity"),
+            OutputActivation::Scaled { min, max } => write!(f, "scaled:{}:{}", min, max),
+            OutputActivation::Softmax => write!(f, "softmax")
+        };
+    }
+}
+
+impl FromStr for OutputActivation {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid output activation: {:?}", text);
+        return match text.split(':').collect::<Vec<_>>()[..] {
+            ["sigmoid"] => Ok(OutputActivation::Sigmoid),
+            ["identity"] => Ok(OutputActivation::Identity),
+            ["scaled", min, max] => Ok(OutputActivation::Scaled {
+                min: min.parse().map_err(|_| invalid())?,
+                max: max.parse().map_err(|_| invalid())?
+            }),
+            ["softmax"] => Ok(OutputActivation::Softmax),
+            _ => Err(invalid())
+        };
+    }
+}
+
+// probabilities are clamped to avoid infinite cross-entropy and gradients of it
+pub const EPSILON:f64 = 1e-15;
+
+// categorical cross-entropy if outputs are probabilities of exclusive classes,
+// binary cross-entropy of each output otherwise
+pub fn cross_entropy(probabilities:&[f64], expected:&[f64], categorical:bool) -> f64 {
+    let mut entropy = 0f64;
+    for (probability, target) in probabilities.iter().zip(expected) {
+        let probability = probability.clamp(EPSILON, 1f64 - EPSILON);
+        if categorical {
+            entropy -= target * probability.ln();
+        } else {
+            entropy -= target * probability.ln() + (1f64 - target) * (1f64 - probability).ln();
+        }
+    }
+    return entropy;
+}
+
+// gradient of `cross_entropy` with respect to the probabilities
+pub fn cross_entropy_gradient(probabilities:&[f64], expected:&[f64], categorical:bool) -> Vec<f64> {
+    return probabilities.iter().zip(expected).map(|(probability, target)| {
+        let probability = probability.clamp(EPSILON, 1f64 - EPSILON);
+        if categorical {
+            return -target / probability;
+        }
+        return (1f64 - target) / (1f64 - probability) - target / probability;
+    }).collect();
+}
+
+// error measure used to calculate fitness of a genome 
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum FitnessFunction {
+    MeanAbsoluteError,
+    // categorical cross-entropy for softmax outputs, binary cross-entropy of each output otherwise
+    CrossEntropy
+}
+
+impl fmt::Display for FitnessFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", match self {
+            FitnessFunction::MeanAbsoluteError => "mae",
+            FitnessFunction::CrossEntropy => "cross-entropy"
+        });
+    }
+}
+
+impl FromStr for FitnessFunction {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        return match text {
+            "mae" => Ok(FitnessFunction::MeanAbsoluteError),
+            "cross-entropy" => Ok(FitnessFunction::CrossEntropy),
+            _ => Err(format!("unknown fitness function: {:?}", text))
+        };
+    }
+}
+
 // easiest: 
 // count inputs and forward only if all are satisfied (cycles can't count) 
 // but also: hand written recursion limit
@@ -36,7 +181,7 @@ fn evaluate_recursive(genome:&Genome, nodes:&mut Vec<Node>, nodes_to_process:Has
                 }
                 nodes[gene.output].value += value * gene.weight;
                 nodes[gene.output].expected_inputs -= 1;
-                if nodes[gene.output].expected_inputs <= 0 && !nodes[gene.output].propagated {
+                if nodes[gene.output].expected_inputs == 0 && !nodes[gene.output].propagated {
                     nodes[gene.output].propagated = true;
                     new_nodes_to_process.insert(gene.output);
                 }
@@ -77,6 +222,30 @@ pub fn evaluate(input:&[f64], problem:&Problem, genome:&mut Genome) -> Vec<f64>
     */
     genome.active_nodes = nodes.iter().filter(|x| x.propagated).count() + problem.outputs;
 
-    return nodes[problem.inputs..problem.inputs+problem.outputs]
-           .into_iter().map(|node| sigmoid(node.value)).collect();
-}
\ No newline at end of file
+    let outputs:Vec<f64> = nodes[problem.inputs..problem.inputs+problem.outputs]
+           .iter().map(|node| node.value).collect();
+    return problem.output_activation.apply(&outputs);
+}
+
+// values of input nodes for a dataset row, first value is bias
+pub fn network_input(input:&[f64]) -> Vec<f64> {
+    let mut network_input = vec![1f64];
+    network_input.extend(input.iter());
+    return network_input;
+}
+
+// evaluates genome on a single dataset row 
+pub fn predict(input:&[f64], problem:&Problem, genome:&mut Genome) -> Vec<f64> {
+    return evaluate(&network_input(input), problem, genome);
+}
+
+// model mapping a dataset row to output values, implemented by genomes and ensembles
+pub trait Predictor {
+    fn predict(&mut self, input:&[f64], problem:&Problem) -> Vec<f64>;
+}
+
+impl Predictor for Genome {
+    fn predict(&mut self, input:&[f64], problem:&Problem) -> Vec<f64> {
+        return predict(input, problem, self);
+    }
+}
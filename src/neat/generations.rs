@@ -1,52 +1,82 @@
 use super::structs::gene::Gene;
 use super::structs::genome::Genome;
 use super::structs::problem::Problem;
+use super::structs::config::Config;
+use super::structs::mutation_rates::MutationRates;
 use rand::Rng;
-use super::evaluation::evaluate;
-use super::visualisation::visualise;
-use super::genetics::{cross, mutate, difference, is_valid};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rand::seq::SliceRandom;
+use super::evaluation::{cross_entropy, predict, FitnessFunction, OutputActivation};
+use super::metrics::{report, Report};
+use super::ensemble::{Combination, Ensemble};
+use super::backpropagation::{train, FineTuning, Inheritance};
+use super::weight_search::{optimise, WeightSearch};
+use super::history::GenerationStats;
+use super::reporting::Reporter;
+use super::termination::{Progress, StopReason};
+use std::time::Instant;
+use std::error::Error;
+use std::path::Path;
+use super::restart::{add_to_hall_of_fame, Restart, RestartStrategy, HALL_OF_FAME_SIZE, RESTART_ATTEMPTS};
+use super::genetics::{cross, mutate, mutate_clone, difference, is_valid, standard_normal};
 
 const NUMBER_OF_SPECIMENS:usize = 1000;
-const TOURNAMENT_SIZE:usize = 20;
 const STAGNATION_TO_EXTINCTION:usize = 20;
-const MIXED_CHILDREN_PART:f64 = 0.2;
 const PERISHED_PART:f64 = 0.7;
-const ITERATIONS:u64 = 10;
 
-fn start_genome(problem:&Problem) -> Genome {
+// every input is connected to every output with a weight drawn from `config.weight_init`
+fn start_genome<R: Rng + ?Sized>(rng: &mut R, problem:&Problem, config:&Config) -> Genome {
     let mut genes = Vec::new();
     for i in 0..problem.inputs {
         for o in 0..problem.outputs {
             genes.push(Gene::new(
                 i,
                 // first nodes are for inputs and latter nodes are for outputs 
-                problem.inputs+o)
+                problem.inputs+o,
+                config.weight_init.sample(rng).clamp(config.min_weight, config.max_weight))
             );
         }
     }
     return  Genome {
         nodes: problem.inputs+problem.outputs,
-        genes: genes,
+        genes,
         fitness_complexity_speciation: 0f64,
         fitness : 0f64,
         fitness_complexity: 0f64,
         validation_fitness: 0f64,
-        active_nodes: 0
+        active_nodes: 0,
+        mutation_rates: None
     };
 }
 
-fn calculate_base_fitness(problem:&Problem, genome:&mut Genome, dataset:&Dataset) -> f64 {
+// mutated start genome, specimens of the first generation and of random restarts
+fn random_genome<R: Rng + ?Sized>(rng: &mut R, problem:&Problem, config:&Config, innovation:u64) -> Genome {
+    let mut genome = start_genome(rng, problem, config);
+    if config.self_adaptive_mutation {
+        genome.mutation_rates = Some(MutationRates::from_config(config));
+    }
+    // initial mutation 
+    mutate(rng, &mut genome, problem, config, innovation);
+    return genome;
+}
+
+// fitness of outputs predicted for every row of the dataset
+pub fn base_fitness(problem:&Problem, predictions:&[Vec<f64>], dataset:&Dataset) -> f64 {
+    if let FitnessFunction::CrossEntropy = problem.fitness_function {
+        let categorical = matches!(problem.output_activation, OutputActivation::Softmax);
+        let mut entropy = 0f64;
+        for (evaluation_result, output) in predictions.iter().zip(dataset.outputs.iter()) {
+            entropy += cross_entropy(evaluation_result, output, categorical);
+        }
+        entropy /= dataset.inputs.len() as f64;
+        // geometric mean of probabilities assigned to expected outputs, always in (0, 1]
+        return (-entropy).exp();
+    }
+
     let mut result = 0f64;
-    for (input, output) in dataset.inputs.iter().zip(dataset.outputs.iter()) {
-        // first value is bias 
-        let mut network_input = vec![1f64];
-        network_input.extend(input.iter());
-
-        let evaluation_result = evaluate(
-            &input,
-            &problem,
-            genome,
-        );
+    for (evaluation_result, output) in predictions.iter().zip(dataset.outputs.iter()) {
         let mut fitness = 0f64;
         for (result, expected) in evaluation_result.iter().zip(output) {
             fitness += f64::abs(result - expected);
@@ -56,7 +86,17 @@ fn calculate_base_fitness(problem:&Problem, genome:&mut Genome, dataset:&Dataset
     }
     result /= dataset.inputs.len() as f64;
     
-    return 1f64 - result;
+    if problem.output_activation.is_bounded() {
+        return 1f64 - result;
+    } else {
+        // error of unbounded outputs can be greater than one 
+        return 1f64 / (1f64 + result);
+    }
+}
+
+pub fn calculate_base_fitness(problem:&Problem, genome:&mut Genome, dataset:&Dataset) -> f64 {
+    let predictions:Vec<Vec<f64>> = dataset.inputs.iter().map(|input| predict(input, problem, genome)).collect();
+    return base_fitness(problem, &predictions, dataset);
 }
 
 fn calculate_fitness(problem:&Problem, genome:&mut Genome, species_size: usize, training_dataset:&Dataset) {
@@ -68,107 +108,399 @@ fn calculate_fitness(problem:&Problem, genome:&mut Genome, species_size: usize,
     genome.fitness_complexity_speciation /= species_size as f64;
 }
 
-pub fn tournament<'a, R: Rng + ?Sized>(rng: &mut R, specimens: &'a Vec<Genome>) -> &'a Genome {
-    let mut best = &specimens[rng.gen_range(0..specimens.len())]; 
-    for _ in 0..TOURNAMENT_SIZE {
-        let contestant = &specimens[rng.gen_range(0..specimens.len())]; 
-        if contestant.fitness_complexity_speciation > best.fitness_complexity_speciation {
-            best = contestant;
+// index of the specimen with the highest value of the key
+fn best_index(specimens:&[Genome], key:fn(&Genome) -> f64) -> Option<usize> {
+    return (0..specimens.len()).max_by(|&a, &b| key(&specimens[a]).partial_cmp(&key(&specimens[b])).unwrap());
+}
+
+// trains weights of the genome and scores it with them, 
+// tuned weights are kept only with Lamarckian inheritance,
+// returns the number of passes over the training dataset, each epoch is counted as one evaluation
+fn fine_tune(problem:&Problem, config:&Config, tuning:&FineTuning, genome:&mut Genome, species_size:usize, training_dataset:&Dataset) -> u64 {
+    let mut tuned = genome.clone();
+    if !train(&mut tuned, problem, config, training_dataset, tuning.epochs, tuning.learning_rate) {
+        return 0;
+    }
+    calculate_fitness(problem, &mut tuned, species_size, training_dataset);
+    match tuning.inheritance {
+        Inheritance::Lamarckian => *genome = tuned,
+        Inheritance::Baldwinian => {
+            genome.fitness = tuned.fitness;
+            genome.fitness_complexity = tuned.fitness_complexity;
+            genome.fitness_complexity_speciation = tuned.fitness_complexity_speciation;
         }
     }
-    return best;
+    return tuning.epochs as u64 + 1;
 }
 
 pub struct Specie {
+    // unique in a single run, used to track species across generations
+    pub id: usize,
     pub representative: Genome,
     pub specimens: Vec<Genome>,
-    pub best_fitness: f64
+    pub best_fitness: f64,
+    // specimen with the best validation fitness since the specie was created, 
+    // None until the specie is evaluated
+    pub champion: Option<Genome>
     // calculate number of children using best fitness 
 }
 
 pub type DataFrame = std::vec::Vec<std::vec::Vec<f64>>;
 
+// reads a csv file with column names in the first row and numbers in the others
+fn read_data_frame<P: AsRef<Path>>(path:P) -> Result<(Vec<String>, DataFrame), Box<dyn Error>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let header = reader.headers()?.iter().map(|x| x.to_string()).collect();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        rows.push(record?.iter().map(|x| x.parse::<f64>()).collect::<Result<Vec<_>, _>>()?);
+    }
+    return Ok((header, rows));
+}
+
 #[derive(Debug)]
+#[derive(Clone)]
 pub struct Dataset {
     pub inputs: DataFrame,
-    pub outputs: DataFrame
+    pub outputs: DataFrame,
+    // column names read from the csv headers 
+    pub input_names: Vec<String>,
+    pub output_names: Vec<String>
+}
+
+impl Dataset {
+    // rows of both files are matched by their order
+    pub fn load<P: AsRef<Path>>(inputs_path:P, outputs_path:P) -> Result<Dataset, Box<dyn Error>> {
+        let (input_names, inputs) = read_data_frame(inputs_path)?;
+        let (output_names, outputs) = read_data_frame(outputs_path)?;
+        return Ok(Dataset { inputs, outputs, input_names, output_names });
+    }
+
+    pub fn problem(&self, config:&Config) -> Problem {
+        // +1 for the bias, counts are taken from headers so files without rows can be used
+        return Problem {
+            inputs:self.input_names.len()+1, 
+            outputs:self.output_names.len(), 
+            output_activation:config.output_activation,
+            fitness_function:config.fitness_function
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.inputs.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.inputs.is_empty();
+    }
+
+    // creates a new dataset containing only rows with given indices 
+    pub fn subset(&self, indices:&[usize]) -> Dataset {
+        return Dataset {
+            inputs: indices.iter().map(|&i| self.inputs[i].clone()).collect(),
+            outputs: indices.iter().map(|&i| self.outputs[i].clone()).collect(),
+            input_names: self.input_names.clone(),
+            output_names: self.output_names.clone()
+        };
+    }
+
+    // shuffles rows and splits them into training, validation and test datasets, 
+    // parts are fractions of all rows, training rows are used for validation if its part is zero 
+    pub fn split<R: Rng + ?Sized>(&self, rng: &mut R, validation_part:f64, test_part:f64) -> (Dataset, Dataset, Dataset) {
+        let mut indices:Vec<usize> = (0..self.len()).collect();
+        indices.shuffle(rng);
+        // at least one row is left for training
+        let test_len = ((self.len() as f64 * test_part).round() as usize).min(self.len() - 1);
+        let validation_len = ((self.len() as f64 * validation_part).round() as usize).min(self.len() - 1 - test_len);
+        let test = self.subset(&indices[..test_len]);
+        let validation = self.subset(&indices[test_len..test_len + validation_len]);
+        let training = self.subset(&indices[test_len + validation_len..]);
+        if validation.is_empty() {
+            return (training.clone(), training, test);
+        }
+        return (training, validation, test);
+    }
+
+    // random subset of the dataset without repetitions, 
+    // None if requested size covers the whole dataset 
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, size:usize) -> Option<Dataset> {
+        if size >= self.len() {
+            return None;
+        }
+        return Some(self.subset(&index::sample(rng, self.len(), size).into_vec()));
+    }
 }
 
-// struct used to reduce number of passed arguments 
-// between functions in this module
-pub struct Generations<'a, R: Rng + ?Sized> {
-    random: &'a mut R,
-    problem:&'a Problem,
-    training_dataset: &'a Dataset,
-    validation_dataset: &'a Dataset,
+// population of genomes evolved one generation at a time, 
+// generation loop can be driven by `run` or by the caller using `step`
+pub struct Population {
+    random: StdRng,
+    config: Config,
+    problem: Problem,
+    // rows of training dataset used in the current iteration
+    batch: Option<Dataset>,
     species : Vec<Specie>,
+    // champion chosen by validation fitness
     best: Genome,
+    // generations since the best training fitness improved, leads to extinction
     fitness_stagnant: usize,
+    best_training_fitness: f64,
+    // best evaluated genomes by training fitness, restarts are seeded from them
+    hall_of_fame: Vec<Genome>,
     iteration: u64,
 
     children : Vec<Genome>,
     species_fitness_sum: f64,
-    iteration_best:Genome
+    iteration_best:Genome,
+    next_specie_id: usize,
+    history: Vec<GenerationStats>,
+    reporters: Vec<Box<dyn Reporter>>,
+    // set when the first generation starts
+    start_time: Option<Instant>,
+    evaluations: u64,
+    last_improvement: u64,
+    stop_reason: Option<StopReason>
 }
 
-pub fn run<R: Rng + ?Sized>(mut rng: &mut R, training_dataset: &Dataset, validation_dataset: &Dataset) -> Genome {
-    
-    // +1 for the bias 
-    let problem = Problem {inputs:training_dataset.inputs[0].len()+1, outputs:training_dataset.outputs[0].len()};
-    println!("{:?}", problem);
+pub struct RunResult {
+    pub best: Genome,
+    // statistics of every evaluated generation
+    pub history: Vec<GenerationStats>,
+    // metrics of the best genome on the validation dataset
+    pub report: Report,
+    // None if the population was finished before any stop condition was met
+    pub stop_reason: Option<StopReason>,
+    // champions of the best species if `config.ensemble_size` is set
+    pub ensemble: Option<Ensemble>
+}
+
+pub fn run(config: &Config, training_dataset: &Dataset, validation_dataset: &Dataset, 
+    reporters: Vec<Box<dyn Reporter>>) -> RunResult {
     
+    let mut population = Population::new(config.clone(), training_dataset.problem(config));
+    for reporter in reporters {
+        population.add_reporter(reporter);
+    }
+
+    loop {
+        population.step(training_dataset, validation_dataset);
+        if population.should_stop() {
+            break;
+        }
+    }
+    if let Some(tuning) = config.fine_tuning.as_ref().filter(|tuning| tuning.champion) {
+        population.fine_tune_best(training_dataset, validation_dataset, tuning.epochs, tuning.learning_rate);
+    }
+    if let Some(search) = &config.weight_search {
+        population.optimise_best(training_dataset, validation_dataset, search);
+    }
+
+    return population.finish(validation_dataset);
+}
+
+impl Population {
+
+// random generator is seeded with `config.seed` if it's set
+pub fn new(config:Config, problem:Problem) -> Population {
+    if let Err(error) = config.validate() {
+        panic!("invalid config: {}", error);
+    }
+    let mut random = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy()
+    };
+
     // create one start specie 
     let species : Vec<Specie> = vec![Specie {
-        representative: start_genome(&problem),
-        specimens: (0..NUMBER_OF_SPECIMENS).map(|_| {
-            let mut genome = start_genome(&problem);
-            // initial mutation 
-            mutate(&mut rng, &mut genome, &problem, 0);
-            return genome;
-        }).collect(),
-        best_fitness:0.
+        id: 0,
+        representative: start_genome(&mut random, &problem, &config),
+        specimens: (0..NUMBER_OF_SPECIMENS).map(|_| random_genome(&mut random, &problem, &config, 0)).collect(),
+        best_fitness:0.,
+        champion: None
     }];
 
-    let mut _self = Generations {
+    return Population {
         best:species[0].representative.clone(), 
         iteration_best:species[0].representative.clone(), 
         species,
         fitness_stagnant:0,
+        best_training_fitness: 0f64,
+        hall_of_fame: Vec::new(),
         children: vec![],
-        problem:&problem,random:rng,
-        training_dataset,
-        validation_dataset,
+        problem,
+        random,
+        config,
+        batch: None,
         iteration: 0,
-        species_fitness_sum: 0f64
+        species_fitness_sum: 0f64,
+        next_specie_id: 1,
+        history: Vec::new(),
+        reporters: Vec::new(),
+        start_time: None,
+        evaluations: 0,
+        last_improvement: 0,
+        stop_reason: None
     };
+}
 
-    for iteration in 1..ITERATIONS {
-        _self.iteration = iteration;
-        _self.iteration();
+pub fn add_reporter(&mut self, reporter:Box<dyn Reporter>) {
+    self.reporters.push(reporter);
+}
+
+// evaluates current specimens and replaces them with the next generation
+pub fn step(&mut self, training_dataset:&Dataset, validation_dataset:&Dataset) {
+    if self.iteration == 0 {
+        self.start_time = Some(Instant::now());
+        for reporter in self.reporters.iter_mut() {
+            reporter.run_start(&self.problem);
+            reporter.specie_created(0, 0);
+        }
+    }
+    self.iteration += 1;
+    self.iteration(training_dataset, validation_dataset);
+}
+
+// checks stop conditions from the config and asks reporters whether the run should end,
+// the reason is kept and returned in the run result
+pub fn should_stop(&mut self) -> bool {
+    let progress = Progress {
+        generation: self.iteration,
+        training_fitness: self.history.last().map(|stats| stats.best_fitness).unwrap_or(0f64),
+        validation_fitness: self.best.validation_fitness,
+        elapsed: self.start_time.map(|time| time.elapsed()).unwrap_or_default(),
+        evaluations: self.evaluations,
+        generations_without_improvement: self.iteration - self.last_improvement
+    };
+    if let Some(condition) = self.config.stop_conditions.iter().find(|condition| condition.is_met(&progress)) {
+        self.stop_reason = Some(StopReason::Condition(condition.clone()));
+    } else if self.reporters.iter_mut().any(|reporter| reporter.should_stop()) {
+        self.stop_reason = Some(StopReason::Reporter);
     }
+    return self.stop_reason.is_some();
+}
+
+pub fn stop_reason(&self) -> Option<&StopReason> {
+    return self.stop_reason.as_ref();
+}
+
+// reports metrics of the best genome and notifies reporters that the run has ended
+pub fn finish(mut self, validation_dataset:&Dataset) -> RunResult {
+    let report = report(self.config.task, &self.problem, &mut self.best, validation_dataset);
+    let ensemble = self.config.ensemble_size
+        .and_then(|size| self.ensemble(size, self.config.ensemble_combination, validation_dataset));
+    let result = RunResult { best: self.best, history: self.history, report, stop_reason: self.stop_reason, ensemble };
+    for reporter in self.reporters.iter_mut() {
+        reporter.run_end(&result);
+    }
+    return result;
+}
+
+// trains weights of the best genome with backpropagation, 
+// they are kept only if validation fitness doesn't get worse
+pub fn fine_tune_best(&mut self, training_dataset:&Dataset, validation_dataset:&Dataset, epochs:usize, learning_rate:f64) {
+    let mut tuned = self.best.clone();
+    if !train(&mut tuned, &self.problem, &self.config, training_dataset, epochs, learning_rate) {
+        return;
+    }
+    // each epoch is counted as one evaluation
+    self.evaluations += epochs as u64 + 1;
+    calculate_fitness(&self.problem, &mut tuned, 1, training_dataset);
+    tuned.validation_fitness = calculate_base_fitness(&self.problem, &mut tuned, validation_dataset);
+    if tuned.validation_fitness >= self.best.validation_fitness {
+        self.best = tuned;
+        for reporter in self.reporters.iter_mut() {
+            reporter.new_best(self.iteration, &self.best);
+        }
+    }
+}
+
+// searches weights of the best genome without changing its topology,
+// they are kept only if validation fitness doesn't get worse
+pub fn optimise_best(&mut self, training_dataset:&Dataset, validation_dataset:&Dataset, search:&WeightSearch) {
+    let (mut optimised, evaluations) = optimise(&mut self.random, &self.best, &self.problem, &self.config, training_dataset, search);
+    self.evaluations += evaluations as u64 + 1;
+    calculate_fitness(&self.problem, &mut optimised, 1, training_dataset);
+    optimised.validation_fitness = calculate_base_fitness(&self.problem, &mut optimised, validation_dataset);
+    if optimised.validation_fitness >= self.best.validation_fitness {
+        self.best = optimised;
+        for reporter in self.reporters.iter_mut() {
+            reporter.new_best(self.iteration, &self.best);
+        }
+    }
+}
+
+// champions of at most `size` species with the best validation fitness,
+// None if no specie has been evaluated yet
+pub fn ensemble(&self, size:usize, combination:Combination, validation_dataset:&Dataset) -> Option<Ensemble> {
+    let mut champions:Vec<Genome> = self.species.iter().filter_map(|specie| specie.champion.clone()).collect();
+    if champions.is_empty() {
+        return None;
+    }
+    champions.sort_by(|a, b| b.validation_fitness.partial_cmp(&a.validation_fitness).unwrap());
+    champions.truncate(size);
+    return Some(Ensemble::new(champions, combination, &self.problem, validation_dataset));
+}
 
-    println!("[e]\tbest fitness c: {:.5}, \tbest fitness: {:.5}, \nnodes: {:.5}", 
-        _self.best.fitness_complexity, _self.best.validation_fitness, _self.best.active_nodes);
+// number of completed generations
+pub fn generation(&self) -> u64 {
+    return self.iteration;
+}
+
+pub fn species(&self) -> &[Specie] {
+    return &self.species;
+}
+
+// genome with the best validation fitness found so far
+pub fn best(&self) -> &Genome {
+    return &self.best;
+}
 
-    visualise(&_self.best, &_self.problem);
-    return _self.best;
+// all specimens of the current generation
+pub fn genomes(&self) -> impl Iterator<Item = &Genome> {
+    return self.species.iter().flat_map(|specie| specie.specimens.iter());
 }
 
-impl<R: Rng + ?Sized> Generations<'_, R> {
+pub fn history(&self) -> &[GenerationStats] {
+    return &self.history;
+}
+
+pub fn problem(&self) -> &Problem {
+    return &self.problem;
+}
+
+pub fn config(&self) -> &Config {
+    return &self.config;
+}
 
-fn iteration(&mut self) {
+// parameters can be changed between generations
+pub fn config_mut(&mut self) -> &mut Config {
+    return &mut self.config;
+}
+
+fn iteration(&mut self, training_dataset:&Dataset, validation_dataset:&Dataset) {
+    for reporter in self.reporters.iter_mut() {
+        reporter.generation_start(self.iteration);
+    }
     self.children.clear();
     self.iteration_best = self.species[0].representative.clone();
 
     // [Extinction to two specimen due to stagnation]
-    if self.fitness_stagnant > STAGNATION_TO_EXTINCTION {
+    if self.fitness_stagnant > STAGNATION_TO_EXTINCTION && !matches!(self.config.restart, RestartStrategy::None) {
         self.great_extinction(self.iteration);
     } else {
         self.species_fitness_sum = 0.;
 
-        self.evaluate_species();    
+        self.batch = match self.config.batch_size {
+            Some(size) => training_dataset.sample(&mut self.random, size),
+            None => None
+        };
+        self.evaluate_species(training_dataset, validation_dataset);
+        let stats = GenerationStats::collect(self.iteration, &self.species, self.config.speciation_threshold);
+        for reporter in self.reporters.iter_mut() {
+            reporter.generation_evaluated(&stats);
+        }
+        self.history.push(stats);
         self.eliminate_and_reproduce();
-        self.mixed_children();
 
         for specie in self.species.iter_mut() {
             specie.specimens.clear();
@@ -176,24 +508,26 @@ fn iteration(&mut self) {
     }
     self.assign_children_to_species();
 
+    for specie in self.species.iter().filter(|s| s.specimens.is_empty()) {
+        for reporter in self.reporters.iter_mut() {
+            reporter.specie_extinct(self.iteration, specie.id);
+        }
+    }
     self.species.retain(|s| !s.specimens.is_empty());
 
     // speciation
-    println!(
-        "[{}]\tbest fitness c: {:.5}, \tbest fitness: {:.5}, \tnodes: {:.5}, \tspecies: {:.5}", 
-        self.iteration, 
-        self.iteration_best.fitness_complexity, self.iteration_best.validation_fitness, 
-        self.iteration_best.active_nodes, self.species.len());
+    for reporter in self.reporters.iter_mut() {
+        reporter.generation_end(self.iteration, &self.iteration_best, &self.species);
+    }
 
     self.fitness_stagnant += 1;
 }
 
 fn eliminate_and_reproduce(&mut self) {
-     // [Eliminate and reproduce speciment]
+    let selection = self.config.selection.selection();
+     // [Eliminate the weakest specimens]
      for specie in self.species.iter_mut() {
         let specimens = &mut specie.specimens;
-        
-        // [Eliminate the weakest specimens]
         specimens.sort_by(|a, b| {b.fitness_complexity
             .partial_cmp(&a.fitness_complexity).unwrap()});
 
@@ -203,88 +537,130 @@ fn eliminate_and_reproduce(&mut self) {
                 specimens.remove(specimens.len()  - 1);
             }
         }
-        
-        // [Create children of the specie]
+    }
+
+    // [Create children of species]
+    for (index, specie) in self.species.iter().enumerate() {
         let children_count = (
-            (NUMBER_OF_SPECIMENS as f64 * (1. - MIXED_CHILDREN_PART) 
-            * specie.best_fitness / self.species_fitness_sum) as f64) as usize;
+            NUMBER_OF_SPECIMENS as f64 * specie.best_fitness / self.species_fitness_sum) as usize;
         
-        for _ in 0..children_count {
-            // choose parents
-            let parent_a = tournament(&mut self.random, &specimens);
-            let parent_b = tournament(&mut self.random, &specimens);
-
-            // create child 
-            let mut new_child = cross(&mut self.random, parent_a, parent_b);
-            // mutate it  
-            mutate(&mut self.random, &mut new_child, self.problem, self.iteration);
+        let mut parents = selection.select_many(&mut self.random, &specie.specimens, 2 * children_count);
+        // parents chosen at once can be ordered by fitness
+        parents.shuffle(&mut self.random);
+        for pair in parents.chunks_exact(2) {
+            let mut new_child;
+            if self.random.gen::<f64>() < self.config.mutation_only_part {
+                new_child = mutate_clone(&mut self.random, pair[0], &self.problem, &self.config, self.iteration);
+            } else {
+                let mut mate = pair[1];
+                if self.species.len() > 1 && self.random.gen::<f64>() < self.config.interspecies_mating_chance {
+                    // any specie other than this one
+                    let mut other = self.random.gen_range(0..self.species.len() - 1);
+                    if other >= index {
+                        other += 1;
+                    }
+                    mate = selection.select(&mut self.random, &self.species[other].specimens);
+                }
+                new_child = cross(&mut self.random, pair[0], mate);
+                mutate(&mut self.random, &mut new_child, &self.problem, &self.config, self.iteration);
+            }
             
-            if is_valid(&new_child, self.problem) {
+            if is_valid(&new_child, &self.problem) {
                 self.children.push(new_child);
             }
         }
+    }
+
+    for specie in self.species.iter_mut() {
         // change representative to random specimen
         specie.representative = specie.specimens[self.random.gen_range(0..specie.specimens.len())].clone();
     }
 }
 
-fn evaluate_species(&mut self) { 
+fn evaluate_species(&mut self, full_training_dataset:&Dataset, validation_dataset:&Dataset) { 
     let species_len = self.species.len();
+    // specimen with the best training fitness in this generation, it's copied to the next one
+    let mut elite:Option<Genome> = None;
     for (index, specie) in self.species.iter_mut().enumerate() {
         let specimens = &mut specie.specimens;
         let mut specie_best = specie.representative.clone();
         let specimens_len = specimens.len();
+        let training_dataset = self.batch.as_ref().unwrap_or(full_training_dataset);
         for specimen in specimens.iter_mut() {
-            calculate_fitness(self.problem, specimen, specimens_len, self.training_dataset);
-            specimen.validation_fitness = calculate_base_fitness(self.problem, specimen, self.validation_dataset);
-            if specimen.validation_fitness > self.best.validation_fitness {
-                self.best = specimen.clone();
+            calculate_fitness(&self.problem, specimen, specimens_len, training_dataset);
+            self.evaluations += 1;
+        }
+
+        // mini-batch fitness is noisy so specimen that will survive elimination 
+        // is scored again using all rows
+        let specie_elite = specimens.iter_mut().max_by(|a, b| {a.fitness_complexity
+            .partial_cmp(&b.fitness_complexity).unwrap()});
+        if let Some(specie_elite) = specie_elite {
+            if self.batch.is_some() && self.config.reevaluate_elites {
+                calculate_fitness(&self.problem, specie_elite, specimens_len, full_training_dataset);
+                self.evaluations += 1;
+            }
+            if let Some(tuning) = self.config.fine_tuning.as_ref().filter(|tuning| tuning.elites) {
+                self.evaluations += fine_tune(&self.problem, &self.config, tuning, specie_elite, specimens_len, full_training_dataset);
+            }
+            let size = HALL_OF_FAME_SIZE.max(self.config.restart.seeds());
+            add_to_hall_of_fame(&mut self.hall_of_fame, specie_elite, size);
+        }
+
+        // validation fitness only chooses the champion, selection uses training fitness,
+        // so only the best specimens of the specie by training fitness are validated
+        let candidates = [
+            best_index(specimens, |genome| genome.fitness_complexity),
+            best_index(specimens, |genome| genome.fitness)
+        ];
+        for (position, specimen) in specimens.iter_mut().enumerate() {
+            if specimen.fitness > self.best_training_fitness {
+                self.best_training_fitness = specimen.fitness;
                 self.fitness_stagnant = 0;
-                self.children.push(specimen.clone());
             }
-            if specimen.validation_fitness > specie_best.validation_fitness {
+            if specimen.fitness > specie_best.fitness {
                 specie_best = specimen.clone();
             }
+            if !candidates.contains(&Some(position)) {
+                specimen.validation_fitness = 0f64;
+                continue;
+            }
+            specimen.validation_fitness = calculate_base_fitness(&self.problem, specimen, validation_dataset);
+            if specimen.validation_fitness > self.best.validation_fitness {
+                self.best = specimen.clone();
+                self.last_improvement = self.iteration;
+                for reporter in self.reporters.iter_mut() {
+                    reporter.new_best(self.iteration, &self.best);
+                }
+            }
+            if specie.champion.as_ref().is_none_or(|champion| specimen.validation_fitness > champion.validation_fitness) {
+                specie.champion = Some(specimen.clone());
+            }
             if specimen.validation_fitness > self.iteration_best.validation_fitness {
                 self.iteration_best = specimen.clone();
             }
         }
                 
-        println!("[{}/{}]\tbest fitness c: {:.5}, \tbest fitness: {:.5}, \tnodes: {:.5}, \tspecies: {:.5}",  
-        self.iteration, index, 
-        specie_best.fitness_complexity, specie_best.validation_fitness, 
-        specie_best.active_nodes, species_len);
+        for reporter in self.reporters.iter_mut() {
+            reporter.specie_evaluated(self.iteration, index, &specie_best, species_len);
+        }
 
-        specie.best_fitness = specie_best.validation_fitness;
-        self.species_fitness_sum+=specie_best.validation_fitness;
+        specie.best_fitness = specie_best.fitness;
+        self.species_fitness_sum+=specie_best.fitness;
+        if elite.as_ref().is_none_or(|elite| specie_best.fitness > elite.fitness) {
+            elite = Some(specie_best);
+        }
     }
-}
-
-fn mixed_children(&mut self) {
-    // choose two specimen from two random species in tournament and cross them]
-    for _ in 0..(NUMBER_OF_SPECIMENS as f64 * MIXED_CHILDREN_PART) as usize {
-    // choose parents
-    let population_a = &self.species[self.random.gen_range(0..self.species.len())].specimens;
-    let population_b = &self.species[self.random.gen_range(0..self.species.len())].specimens;
-    let parent_a = tournament(&mut self.random, population_a);
-    let parent_b = tournament(&mut self.random, population_b);
-
-    // create child 
-    let mut new_child = cross(&mut self.random, parent_a, parent_b);
-    // mutate it  
-    mutate(&mut self.random, &mut new_child, self.problem, self.iteration);
-    
-    if is_valid(&new_child, self.problem) {
-        self.children.push(new_child);
+    if let Some(elite) = elite {
+        self.children.push(elite);
     }
 }
-}
 
 fn assign_children_to_species(&mut self) {
     for child in self.children.iter() {
         let mut asssigned = false;
         for specie in self.species.iter_mut() {
-            if difference (child, &specie.representative) < 1.0 {
+            if difference (child, &specie.representative) < self.config.speciation_threshold {
                 specie.specimens.push(child.clone());
                 asssigned = true;
                 break;
@@ -292,48 +668,160 @@ fn assign_children_to_species(&mut self) {
         }
         // create new specie for the child if it doesn't match any
         if !asssigned {
+            for reporter in self.reporters.iter_mut() {
+                reporter.specie_created(self.iteration, self.next_specie_id);
+            }
             self.species.push(Specie {
+                id: self.next_specie_id,
                 representative: child.clone(),
                 specimens: vec![child.clone(), child.clone()],
-                best_fitness:0.
-            })
+                best_fitness:0.,
+                champion: None
+            });
+            self.next_specie_id += 1;
         }
     }
 }
 
 fn great_extinction(&mut self, iteration: u64) {
-    println!("Noah get the boat");
-    let mut specimens:Vec<Genome> = self.species.iter().map(|s| s.specimens.clone()).flatten().collect();
-    specimens.sort_by(|a, b| {b.fitness_complexity.partial_cmp(&a.fitness_complexity).unwrap()});
-    
-    // choose parents
-    let parent_a = &specimens[0];
-    let parent_b = &specimens[1];
-
-    for _ in 0..NUMBER_OF_SPECIMENS {
-        // create child 
-        let mut new_child;
-        loop {
-            new_child = cross(&mut self.random, parent_a, parent_b);
-            // mutate it  
-            mutate(&mut self.random, &mut new_child, self.problem, iteration);
-            
-            if is_valid(&new_child, self.problem) {
-                self.children.push(new_child);
+    let strategy = self.config.restart;
+    // current specimens are children that haven't been evaluated yet, so seeds come
+    // from the hall of fame, validation fitness only chooses the returned genome and isn't used
+    let seeds:Vec<Genome> = match strategy {
+        RestartStrategy::None => return,
+        RestartStrategy::TopK(_) | RestartStrategy::RandomRestart(_) => 
+            self.hall_of_fame.iter().take(strategy.seeds()).cloned().collect(),
+        RestartStrategy::DeltaCoding(_) => self.hall_of_fame.iter()
+            .max_by(|a, b| a.fitness.partial_cmp(&b.fitness).unwrap()).cloned().into_iter().collect()
+    };
+    if seeds.is_empty() {
+        return;
+    }
+
+    let mut restart = Restart { strategy, seeds: seeds.len(), children: 0, rejected: 0, fallbacks: 0 };
+    if let RestartStrategy::RandomRestart(_) = strategy {
+        // hall of fame survives the restart unchanged
+        self.children.extend(seeds.iter().cloned());
+    }
+    while self.children.len() < NUMBER_OF_SPECIMENS {
+        let mut new_child = None;
+        for _ in 0..RESTART_ATTEMPTS {
+            let candidate = match strategy {
+                RestartStrategy::DeltaCoding(sigma) => {
+                    let mut candidate = seeds[0].clone();
+                    for gene in candidate.genes.iter_mut().filter(|gene| gene.enabled) {
+                        gene.weight = (gene.weight + sigma * standard_normal(&mut self.random))
+                            .clamp(self.config.min_weight, self.config.max_weight);
+                    }
+                    candidate
+                },
+                RestartStrategy::RandomRestart(_) => random_genome(&mut self.random, &self.problem, &self.config, iteration),
+                _ => {
+                    // two different seeds, the better one first, if there are two
+                    let mut pair = index::sample(&mut self.random, seeds.len(), 2.min(seeds.len())).into_vec();
+                    pair.sort();
+                    let parent_b = pair.last().unwrap();
+                    let mut candidate = cross(&mut self.random, &seeds[pair[0]], &seeds[*parent_b]);
+                    mutate(&mut self.random, &mut candidate, &self.problem, &self.config, iteration);
+                    candidate
+                }
+            };
+            if is_valid(&candidate, &self.problem) {
+                new_child = Some(candidate);
                 break;
             }
+            restart.rejected += 1;
         }
+        let new_child = new_child.unwrap_or_else(|| {
+            restart.fallbacks += 1;
+            return seeds[0].clone();
+        });
+        self.children.push(new_child);
     }
-    self.species = vec![Specie {
-        representative: parent_a.clone(),
-        specimens: Vec::new(),
-        best_fitness:0.
-    }, Specie {
-        representative: parent_b.clone(),
+    restart.children = self.children.len();
+
+    for reporter in self.reporters.iter_mut() {
+        reporter.extinction(self.iteration, &restart);
+    }
+    for specie in self.species.iter() {
+        for reporter in self.reporters.iter_mut() {
+            reporter.specie_extinct(self.iteration, specie.id);
+        }
+    }
+    self.species = seeds.iter().enumerate().map(|(i, seed)| Specie {
+        id: self.next_specie_id + i,
+        representative: seed.clone(),
         specimens: Vec::new(),
-        best_fitness:0.
+        best_fitness:0.,
+        champion: None
+    }).collect();
+    for specie in self.species.iter() {
+        for reporter in self.reporters.iter_mut() {
+            reporter.specie_created(self.iteration, specie.id);
+        }
     }
-    ];
+    self.next_specie_id += seeds.len();
     self.fitness_stagnant=0;
 }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem() -> Problem {
+        return Problem {
+            inputs: 3,
+            outputs: 1,
+            output_activation: OutputActivation::Sigmoid,
+            fitness_function: FitnessFunction::MeanAbsoluteError
+        };
+    }
+
+    // linearly separable, so training of the start genome improves its fitness
+    fn or() -> Dataset {
+        return Dataset {
+            inputs: vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]],
+            outputs: vec![vec![0.0], vec![1.0], vec![1.0], vec![1.0]],
+            input_names: vec!["a".to_string(), "b".to_string()],
+            output_names: vec!["result".to_string()]
+        };
+    }
+
+    fn tuning(inheritance:Inheritance) -> FineTuning {
+        return FineTuning { epochs: 10, learning_rate: 0.5, elites: true, champion: false, inheritance };
+    }
+
+    // genome tuned by `fine_tune` and the same genome trained directly
+    fn tuned_and_trained(inheritance:Inheritance) -> (Genome, Genome) {
+        let (problem, config, dataset) = (problem(), Config::default(), or());
+        let mut genome = start_genome(&mut StdRng::seed_from_u64(0), &problem, &config);
+        calculate_fitness(&problem, &mut genome, 1, &dataset);
+        let mut trained = genome.clone();
+        assert!(train(&mut trained, &problem, &config, &dataset, 10, 0.5));
+        calculate_fitness(&problem, &mut trained, 1, &dataset);
+
+        let original = genome.clone();
+        assert_eq!(fine_tune(&problem, &config, &tuning(inheritance), &mut genome, 1, &dataset), 11);
+        assert!(trained.fitness > original.fitness);
+        assert_eq!(genome.fitness, trained.fitness);
+        return (genome, trained);
+    }
+
+    fn weights(genome:&Genome) -> Vec<f64> {
+        return genome.genes.iter().map(|gene| gene.weight).collect();
+    }
+
+    #[test]
+    fn lamarckian_tuning_keeps_trained_weights() {
+        let (genome, trained) = tuned_and_trained(Inheritance::Lamarckian);
+        assert_eq!(weights(&genome), weights(&trained));
+    }
+
+    #[test]
+    fn baldwinian_tuning_keeps_original_weights() {
+        let (genome, trained) = tuned_and_trained(Inheritance::Baldwinian);
+        let original = start_genome(&mut StdRng::seed_from_u64(0), &problem(), &Config::default());
+        assert_eq!(weights(&genome), weights(&original));
+        assert_ne!(weights(&genome), weights(&trained));
+    }
+}
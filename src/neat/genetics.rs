@@ -2,12 +2,14 @@ use rand::Rng;
 use super::structs::genome::Genome;
 use super::structs::problem::Problem;
 use super::structs::gene::Gene;
+use super::structs::config::Config;
+use super::structs::mutation_rates::MutationRates;
 use std::cmp::max;
+use std::f64::consts::PI;
 
-const MUTATE_WEIGHT_CHANCE:f64 = 0.15;
-const WEIGHT_CHANGE:f64 = 0.25;
-const INSERT_NODE_CHANCE:f64 = 0.05;
-const INSERT_CONNECTION_CHANCE:f64 = 0.05;
+// chances of self-adaptive mutation are kept in this range 
+const MIN_CHANCE:f64 = 0.001;
+const MAX_CHANCE:f64 = 1.0;
 
 const DISABLE_CONNECTION_CHANCE:f64 = 0f64;
 
@@ -15,32 +17,133 @@ fn choice<R: Rng + ?Sized>(rng: &mut R, chance:f64) -> bool {
     return rng.gen_range(0f64..1f64) < chance;
 }
 
-fn max_edges_in_undirected_graph(n: u64) -> u64 {
-    // assuming there are no self loops and no double edges 
-    return (n*(n-1))/2;
+// standard normal distribution sampled with the Box-Muller transform
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // first value is in (0, 1] so its logarithm is finite
+    let u1 = 1f64 - rng.gen_range(0f64..1f64);
+    let u2 = rng.gen_range(0f64..1f64);
+    return (-2f64 * u1.ln()).sqrt() * (2f64 * PI * u2).cos();
 }
 
-pub fn mutate<R: Rng + ?Sized>(mut rng: &mut R, mut genome:&mut Genome, problem:&Problem, innovation:u64) {
-    let mut genes_to_add:Vec<Gene> = Vec::new();
+// distribution of weights of new connections and replaced weights
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum WeightDistribution {
+    Constant(f64),
+    Uniform { min: f64, max: f64 },
+    Gaussian { mean: f64, sigma: f64 }
+}
+
+impl WeightDistribution {
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        return match self {
+            WeightDistribution::Constant(weight) => *weight,
+            WeightDistribution::Uniform { min, max } if min < max => rng.gen_range(*min..*max),
+            WeightDistribution::Uniform { min, .. } => *min,
+            WeightDistribution::Gaussian { mean, sigma } => mean + sigma * standard_normal(rng)
+        };
+    }
+}
+
+// change added to a mutated weight
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum WeightPerturbation {
+    // drawn uniformly from (-change, change)
+    Uniform(f64),
+    // drawn from the normal distribution with zero mean and given sigma
+    Gaussian(f64)
+}
+
+impl WeightPerturbation {
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        return match self {
+            WeightPerturbation::Uniform(change) if *change > 0f64 => rng.gen_range(-change..*change),
+            WeightPerturbation::Uniform(_) => 0f64,
+            WeightPerturbation::Gaussian(sigma) => sigma * standard_normal(rng)
+        };
+    }
+}
+
+// log-normal self-adaptation known from evolution strategies, 
+// every rate is multiplied by exp(learning_rate * N(0, 1))
+fn adapt_rates<R: Rng + ?Sized>(rng: &mut R, rates:&mut MutationRates, learning_rate:f64) {
+    let mut adapt = |value:f64| value * (learning_rate * standard_normal(rng)).exp();
+    rates.weight_mutation_chance = adapt(rates.weight_mutation_chance).clamp(MIN_CHANCE, MAX_CHANCE);
+    rates.weight_step = adapt(rates.weight_step);
+    rates.insert_node_chance = adapt(rates.insert_node_chance).clamp(MIN_CHANCE, MAX_CHANCE);
+    rates.insert_connection_chance = adapt(rates.insert_connection_chance).clamp(MIN_CHANCE, MAX_CHANCE);
+}
+
+// global rates from the config are used if the genome doesn't have its own
+fn rates(genome:&Genome, config:&Config) -> MutationRates {
+    return genome.mutation_rates.unwrap_or_else(|| MutationRates::from_config(config));
+}
+
+// rates of a genome are mutated first and then used to mutate the genome
+pub fn mutate<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome, problem:&Problem, config:&Config, innovation:u64) {
+    if let Some(rates) = genome.mutation_rates.as_mut() {
+        adapt_rates(rng, rates, config.adaptation_rate);
+    }
+    mutate_weights(rng, genome, config);
+    mutate_structure(rng, genome, problem, config, innovation);
+}
+
+// offspring of a single parent, it keeps weights of the parent and gets only structural mutations,
+// fitness of the parent isn't inherited
+pub fn mutate_clone<R: Rng + ?Sized>(rng: &mut R, parent:&Genome, problem:&Problem, config:&Config, innovation:u64) -> Genome {
+    let mut genome = parent.clone();
+    genome.fitness = 0f64;
+    genome.fitness_complexity = 0f64;
+    genome.fitness_complexity_speciation = 0f64;
+    genome.validation_fitness = 0f64;
+    if let Some(rates) = genome.mutation_rates.as_mut() {
+        adapt_rates(rng, rates, config.adaptation_rate);
+    }
+    mutate_structure(rng, &mut genome, problem, config, innovation);
+    return genome;
+}
+
+// perturbs or replaces weights of genes
+pub fn mutate_weights<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome, config:&Config) {
+    let rates = rates(genome, config);
     for gene in genome.genes.iter_mut() {
-        // if > mutate weight chance 
-        if choice(rng, MUTATE_WEIGHT_CHANCE) {
-            gene.weight = gene.weight + rng.gen_range(-WEIGHT_CHANGE..WEIGHT_CHANGE);
+        if choice(rng, rates.weight_mutation_chance) {
+            if choice(rng, config.weight_replace_chance) {
+                gene.weight = config.weight_init.sample(rng);
+            } else {
+                gene.weight += rates.weight_step * config.weight_perturbation.sample(rng);
+            }
+            gene.weight = gene.weight.clamp(config.min_weight, config.max_weight);
         }
+    }
+}
+
+// adds, deletes, disables and enables nodes and connections, weights of existing genes are kept
+pub fn mutate_structure<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome, problem:&Problem, config:&Config, innovation:u64) {
+    let rates = rates(genome, config);
+    let mut genes_to_add:Vec<Gene> = Vec::new();
+    for gene in genome.genes.iter_mut() {
         if choice(rng, DISABLE_CONNECTION_CHANCE) {
             gene.enabled = false;
+            gene.disabled_at = Some(innovation);
         }
-        if choice(rng, INSERT_NODE_CHANCE) {
+        // splitting a disabled connection would enable its path again, which could close a cycle
+        if gene.enabled && choice(rng, rates.insert_node_chance) {
             gene.enabled = false;
+            gene.disabled_at = Some(innovation);
             genome.nodes+=1;
             // add two connections to and out of the new node 
             genes_to_add.push(
                 Gene {
                     input:gene.input,
                     output: genome.nodes-1,
-                    weight: 1f64,
+                    weight: 1f64.clamp(config.min_weight, config.max_weight),
                     enabled: true,
-                    innovation: innovation
+                    innovation,
+                    disabled_at: None
                 }
             );
             genes_to_add.push(
@@ -49,70 +152,148 @@ pub fn mutate<R: Rng + ?Sized>(mut rng: &mut R, mut genome:&mut Genome, problem:
                     output: gene.output,
                     weight: gene.weight,
                     enabled: true,
-                    innovation: innovation
-                }
-            );
-            genes_to_add.push(
-                Gene {
-                    input: 0,
-                    output: genome.nodes-1,
-                    weight: rng.gen_range(-0.1..0.1),
-                    enabled: true,
-                    innovation: innovation
+                    innovation,
+                    disabled_at: None
                 }
             );
+            // bias connection, unless the split connection already starts in the bias node 
+            if gene.input != 0 {
+                genes_to_add.push(
+                    Gene {
+                        input: 0,
+                        output: genome.nodes-1,
+                        weight: config.weight_init.sample(rng).clamp(config.min_weight, config.max_weight),
+                        enabled: true,
+                        innovation,
+                        disabled_at: None
+                    }
+                );
+            }
         }
     }
     genome.genes.append(&mut genes_to_add);
     
-    if choice(rng, INSERT_CONNECTION_CHANCE) {
-        insert_connection(&mut rng, &mut genome, &problem, innovation);
+    if choice(rng, rates.insert_connection_chance) {
+        insert_connection(rng, genome, problem, config, innovation);
+    }
+    if choice(rng, config.delete_connection_chance) {
+        delete_connection(rng, genome);
+    }
+    if choice(rng, config.delete_node_chance) {
+        delete_node(rng, genome, problem);
+    }
+    if choice(rng, config.reenable_chance) {
+        reenable_connection(rng, genome, problem);
+    }
+    if let Some(lifetime) = config.disabled_gene_lifetime {
+        // innovation of a gene is the generation it was created in, 
+        // it's used when the generation the gene was disabled in isn't known
+        genome.genes.retain(|gene| gene.enabled || gene.disabled_at.unwrap_or(gene.innovation) + lifetime >= innovation);
+    }
+}
+
+// enables one of disabled genes whose input node is evaluated, 
+// the gene is left disabled if it would create a cycle
+fn reenable_connection<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome, problem:&Problem) {
+    let candidates:Vec<usize> = (0..genome.genes.len())
+        .filter(|&i| !genome.genes[i].enabled)
+        .filter(|&i| genome.genes[i].input < problem.inputs || enabled_inputs(genome, genome.genes[i].input) > 0)
+        .collect();
+    if !candidates.is_empty() {
+        let gene = candidates[rng.gen_range(0..candidates.len())];
+        genome.genes[gene].enabled = true;
+        if genome.has_cycles() {
+            genome.genes[gene].enabled = false;
+        } else {
+            genome.genes[gene].disabled_at = None;
+        }
+    }
+}
+
+fn enabled_inputs(genome:&Genome, node:usize) -> usize {
+    return genome.genes.iter().filter(|gene| gene.enabled && gene.output == node).count();
+}
+
+// node is evaluated only after all its enabled inputs are, so every node that has
+// an enabled input keeps at least one, otherwise nodes after it would never be evaluated
+fn delete_connection<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome) {
+    let candidates:Vec<usize> = (0..genome.genes.len())
+        .filter(|&i| !genome.genes[i].enabled || enabled_inputs(genome, genome.genes[i].output) > 1)
+        .collect();
+    if !candidates.is_empty() {
+        genome.genes.remove(candidates[rng.gen_range(0..candidates.len())]);
+    }
+}
+
+// removes a hidden node with all its connections, nodes aren't renumbered 
+// so genes of other genomes keep referring to the same nodes and the removed one is left unused
+fn delete_node<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome, problem:&Problem) {
+    let hidden_nodes = problem.inputs + problem.outputs..genome.nodes;
+    // every node after the deleted one has to keep an enabled input
+    let candidates:Vec<usize> = hidden_nodes.filter(|&node| {
+        let has_genes = genome.genes.iter().any(|gene| gene.input == node || gene.output == node);
+        return has_genes && genome.genes.iter()
+            .filter(|gene| gene.enabled && gene.input == node)
+            .all(|gene| enabled_inputs(genome, gene.output) > 1);
+    }).collect();
+    if !candidates.is_empty() {
+        let node = candidates[rng.gen_range(0..candidates.len())];
+        genome.genes.retain(|gene| gene.input != node && gene.output != node);
     }
 }
 
 pub fn is_valid(genome:&Genome, problem:&Problem) -> bool {
-    return !genome.has_cycles() && genome.genes.iter().fold(true, |acc, gene| acc && gene.output >= problem.inputs);
-}
-
-fn insert_connection<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome, problem:&Problem, innovation:u64) {
-    // Lazy generate all options and choose stopping point
-
-    let mut possible_connections = max_edges_in_undirected_graph(genome.nodes as u64) as i64;
-    // subtract connections from each node to inputs and itself 
-    possible_connections -= ((problem.inputs + 1) * genome.nodes) as i64;
-    // subtract already existing connections 
-    possible_connections -= genome.genes.len() as i64;
-
-    if possible_connections > 0 {
-        let new_connection = rng.gen_range(0..possible_connections);
-        
-        // iterate over possible connections, increment if connection doesn't exist 
-        // if index == new_path add connection 
-        let mut index = 0;
-        for input in 0..genome.nodes-1 {
-            // output can't lead to input node
-            for output in problem.inputs..genome.nodes {
-                if input != output && !genome.has_connection(input, output) {   
-                    if index == new_connection { 
-                        genome.genes.push(Gene{
-                            input, output,
-                            weight: 0.0,
-                            enabled: true,
-                            innovation:innovation
-                        });
-                        if genome.has_cycles() {
-                            // graph created by the genome is cyclical 
-                            // but we don't want to create this connection later again 
-                            // so the best option is to simply disable it 
-                            let last_gene = genome.genes.len()-1;
-                            genome.genes[last_gene].enabled = false;
-                        }
-                    }
-                    index += 1;
-                }
+    return !genome.has_cycles() && genome.genes.iter().all(|gene| gene.output >= problem.inputs);
+}
+
+// nodes reachable from the given node through enabled genes, including the node itself
+fn reachable_nodes(successors:&[Vec<usize>], node:usize) -> Vec<bool> {
+    let mut reachable = vec![false; successors.len()];
+    let mut stack = vec![node];
+    reachable[node] = true;
+    while let Some(current) = stack.pop() {
+        for &next in successors[current].iter() {
+            if !reachable[next] {
+                reachable[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    return reachable;
+}
+
+// adds a connection chosen uniformly from all pairs of nodes that aren't connected yet,
+// connection can't lead to an input node, start in a node that is never evaluated or create a cycle
+fn insert_connection<R: Rng + ?Sized>(rng: &mut R, genome:&mut Genome, problem:&Problem, config:&Config, innovation:u64) {
+    let mut successors = vec![Vec::new(); genome.nodes];
+    let mut has_input = vec![false; genome.nodes];
+    for gene in genome.genes.iter().filter(|gene| gene.enabled) {
+        successors[gene.input].push(gene.output);
+        has_input[gene.output] = true;
+    }
+
+    let mut candidates = Vec::new();
+    for output in problem.inputs..genome.nodes {
+        // connection from a node reachable from the output would close a cycle
+        let reachable = reachable_nodes(&successors, output);
+        for input in 0..genome.nodes {
+            let evaluated = input < problem.inputs || has_input[input];
+            if evaluated && !reachable[input] && !genome.has_connection(input, output) {
+                candidates.push((input, output));
             }
         }
     }
+
+    if !candidates.is_empty() {
+        let (input, output) = candidates[rng.gen_range(0..candidates.len())];
+        genome.genes.push(Gene {
+            input, output,
+            weight: config.weight_init.sample(rng).clamp(config.min_weight, config.max_weight),
+            enabled: true,
+            innovation,
+            disabled_at: None
+        });
+    }
 }
 
 pub fn cross<R: Rng + ?Sized>(rng: &mut R, genome_a:&Genome, genome_b:&Genome) -> Genome {
@@ -127,7 +308,12 @@ pub fn cross<R: Rng + ?Sized>(rng: &mut R, genome_a:&Genome, genome_b:&Genome) -
         fitness_complexity: 0f64,
         fitness_complexity_speciation : 0f64,
         validation_fitness: 0f64,
-        active_nodes: 0
+        active_nodes: 0,
+        // rates of both parents are averaged
+        mutation_rates: match (genome_a.mutation_rates, genome_b.mutation_rates) {
+            (Some(a), Some(b)) => MutationRates::mean([a, b].iter()),
+            (a, b) => a.or(b)
+        }
     };
 
     while a_index < genome_a.genes.len() && b_index < genome_b.genes.len() {
@@ -219,4 +405,77 @@ pub fn difference (genome_a:&Genome, genome_b:&Genome) -> f64 {
                 + genome_b.genes.len() as i64 - 1 - b_index as i64; 
 
     return C1 * (excess as f64) / (n as f64) + C2 * (disjoint as f64) / (n as f64) + C3 * w;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::evaluation::{FitnessFunction, OutputActivation};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::HashSet;
+
+    fn problem() -> Problem {
+        return Problem {
+            inputs: 3,
+            outputs: 2,
+            output_activation: OutputActivation::Sigmoid,
+            fitness_function: FitnessFunction::MeanAbsoluteError
+        };
+    }
+
+    fn genome(nodes:usize, connections:&[(usize, usize)]) -> Genome {
+        return Genome {
+            nodes,
+            genes: connections.iter().map(|&(input, output)| Gene::new(input, output, 1f64)).collect(),
+            active_nodes: 0,
+            fitness: 0f64,
+            fitness_complexity: 0f64,
+            fitness_complexity_speciation: 0f64,
+            validation_fitness: 0f64,
+            mutation_rates: None
+        };
+    }
+
+    fn assert_invariants(genome:&Genome, problem:&Problem) {
+        let mut pairs = HashSet::new();
+        for gene in genome.genes.iter() {
+            assert!(pairs.insert((gene.input, gene.output)), "duplicate connection {} -> {}", gene.input, gene.output);
+            assert!(gene.output >= problem.inputs, "connection {} -> {} leads to an input", gene.input, gene.output);
+        }
+        assert!(!genome.has_cycles());
+    }
+
+    #[test]
+    fn mutated_genomes_stay_valid() {
+        let problem = problem();
+        let config = Config {
+            insert_node_chance: 0.02,
+            insert_connection_chance: 1.0,
+            delete_connection_chance: 0.2,
+            delete_node_chance: 0.1,
+            reenable_chance: 0.3,
+            ..Config::default()
+        };
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut genome = genome(5, &[(0, 3), (1, 3), (2, 4)]);
+            for generation in 1..50 {
+                mutate(&mut rng, &mut genome, &problem, &config, generation);
+                assert_invariants(&genome, &problem);
+            }
+        }
+    }
+
+    #[test]
+    fn insert_connection_skips_fully_connected_genome() {
+        let problem = problem();
+        // inputs 0-2, outputs 3-4 and hidden node 5 feeding both outputs, 
+        // any other connection would lead to an input or close a cycle
+        let connections = [(0, 3), (1, 3), (2, 3), (0, 4), (1, 4), (2, 4), (0, 5), (1, 5), (2, 5), (5, 3), (5, 4), (3, 4)];
+        let mut full = genome(6, &connections);
+        let mut rng = StdRng::seed_from_u64(0);
+        insert_connection(&mut rng, &mut full, &problem, &Config::default(), 1);
+        assert_eq!(full.genes.len(), connections.len());
+    }
+}
@@ -0,0 +1,254 @@
+use super::generations::Specie;
+use super::structs::mutation_rates::MutationRates;
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct SpecieStats {
+    pub id: usize,
+    pub size: usize,
+    pub best_fitness: f64
+}
+
+// summary of a single evaluated generation
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct GenerationStats {
+    pub generation: u64,
+    // training fitness of specimens
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+    pub median_fitness: f64,
+    // best validation fitness in this generation
+    pub validation_fitness: f64,
+    pub mean_nodes: f64,
+    pub mean_genes: f64,
+    // enabled genes divided by all genes of all specimens
+    pub enabled_ratio: f64,
+    // mean rates of specimens if mutation is self-adaptive
+    pub mutation_rates: Option<MutationRates>,
+    pub species_count: usize,
+    pub speciation_threshold: f64,
+    pub species: Vec<SpecieStats>
+}
+
+impl GenerationStats {
+    // should be called after specimens are evaluated and before they are eliminated
+    pub fn collect(generation:u64, species:&[Specie], speciation_threshold:f64) -> GenerationStats {
+        let mut fitness:Vec<f64> = species.iter().flat_map(|s| s.specimens.iter().map(|g| g.fitness)).collect();
+        fitness.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let count = usize::max(fitness.len(), 1) as f64;
+        let median = if fitness.is_empty() {
+            0f64
+        } else if fitness.len() % 2 == 1 {
+            fitness[fitness.len() / 2]
+        } else {
+            (fitness[fitness.len() / 2 - 1] + fitness[fitness.len() / 2]) / 2f64
+        };
+        let specimens = || species.iter().flat_map(|s| s.specimens.iter());
+        let genes = specimens().map(|g| g.genes.len()).sum::<usize>();
+        let enabled_genes = specimens().map(|g| g.genes.iter().filter(|gene| gene.enabled).count()).sum::<usize>();
+
+        return GenerationStats {
+            generation,
+            best_fitness: fitness.last().cloned().unwrap_or(0f64),
+            mean_fitness: fitness.iter().sum::<f64>() / count,
+            median_fitness: median,
+            validation_fitness: specimens().map(|g| g.validation_fitness).fold(0f64, f64::max),
+            mean_nodes: specimens().map(|g| g.active_nodes as f64).sum::<f64>() / count,
+            mean_genes: specimens().map(|g| g.active_genes() as f64).sum::<f64>() / count,
+            enabled_ratio: if genes > 0 { enabled_genes as f64 / genes as f64 } else { 1f64 },
+            mutation_rates: MutationRates::mean(specimens().filter_map(|g| g.mutation_rates.as_ref())),
+            species_count: species.len(),
+            speciation_threshold,
+            species: species.iter().map(|s| SpecieStats {
+                id: s.id,
+                size: s.specimens.len(),
+                best_fitness: s.specimens.iter().map(|g| g.fitness).fold(0f64, f64::max)
+            }).collect()
+        };
+    }
+}
+
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 300.0;
+const CHART_MARGIN: f64 = 50.0;
+const PALETTE: [&str; 10] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd",
+    "#8c564b", "#e377c2", "#7f7f7f", "#bcbd22", "#17becf"
+];
+
+const HEADER: &str = "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+    <meta charset=\"UTF-8\">
+    <title>Training progress</title>
+    <style>
+        body { font: 14px sans-serif; }
+        .axis { stroke: #000; stroke-width: 1; }
+        .tick { font: 10px sans-serif; fill: #444; }
+        .legend { font: 12px sans-serif; }
+    </style>
+</head>
+<body>
+";
+
+const FOOTER: &str = "</body>
+</html>
+";
+
+// maps generation and value to chart coordinates
+struct Scale {
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64
+}
+
+impl Scale {
+    fn new(generations:&[f64], values:&[f64]) -> Scale {
+        let fold = |values:&[f64], start:f64, f:fn(f64, f64) -> f64| values.iter().cloned().fold(start, f);
+        let mut scale = Scale {
+            min_x: fold(generations, f64::INFINITY, f64::min),
+            max_x: fold(generations, f64::NEG_INFINITY, f64::max),
+            min_y: f64::min(fold(values, f64::INFINITY, f64::min), 0f64),
+            max_y: fold(values, f64::NEG_INFINITY, f64::max)
+        };
+        // avoid division by zero for constant series
+        if scale.max_x <= scale.min_x {
+            scale.max_x = scale.min_x + 1f64;
+        }
+        if scale.max_y <= scale.min_y {
+            scale.max_y = scale.min_y + 1f64;
+        }
+        return scale;
+    }
+
+    fn x(&self, value:f64) -> f64 {
+        return CHART_MARGIN + (value - self.min_x) / (self.max_x - self.min_x) * (CHART_WIDTH - 2.0 * CHART_MARGIN);
+    }
+
+    fn y(&self, value:f64) -> f64 {
+        return CHART_HEIGHT - CHART_MARGIN - (value - self.min_y) / (self.max_y - self.min_y) * (CHART_HEIGHT - 2.0 * CHART_MARGIN);
+    }
+}
+
+fn axes(svg:&mut String, scale:&Scale) {
+    let (left, bottom) = (scale.x(scale.min_x), scale.y(scale.min_y));
+    writeln!(svg, "<line class=\"axis\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>",
+        left, bottom, scale.x(scale.max_x), bottom).unwrap();
+    writeln!(svg, "<line class=\"axis\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>",
+        left, bottom, left, scale.y(scale.max_y)).unwrap();
+    for value in [scale.min_x, scale.max_x] {
+        writeln!(svg, "<text class=\"tick\" x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            scale.x(value), bottom + 15.0, value).unwrap();
+    }
+    for value in [scale.min_y, scale.max_y] {
+        writeln!(svg, "<text class=\"tick\" x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{:.3}</text>",
+            left - 5.0, scale.y(value) + 3.0, value).unwrap();
+    }
+}
+
+fn legend(svg:&mut String, names:&[(String, &str)]) {
+    for (i, (name, color)) in names.iter().enumerate() {
+        let x = CHART_MARGIN + i as f64 * 150.0;
+        writeln!(svg, "<rect x=\"{:.1}\" y=\"10\" width=\"12\" height=\"12\" fill=\"{}\"/>", x, color).unwrap();
+        writeln!(svg, "<text class=\"legend\" x=\"{:.1}\" y=\"21\">{}</text>", x + 16.0, name).unwrap();
+    }
+}
+
+fn line_chart(title:&str, generations:&[f64], series:&[(&str, Vec<f64>)]) -> String {
+    let all_values:Vec<f64> = series.iter().flat_map(|(_, values)| values.iter().cloned()).collect();
+    let scale = Scale::new(generations, &all_values);
+    let mut svg = String::new();
+    writeln!(svg, "<h2>{}</h2>", title).unwrap();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">", CHART_WIDTH, CHART_HEIGHT).unwrap();
+    axes(&mut svg, &scale);
+    for (i, (_, values)) in series.iter().enumerate() {
+        let points:Vec<String> = generations.iter().zip(values.iter())
+            .map(|(x, y)| format!("{:.1},{:.1}", scale.x(*x), scale.y(*y))).collect();
+        writeln!(svg, "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>",
+            PALETTE[i % PALETTE.len()], points.join(" ")).unwrap();
+    }
+    let names:Vec<(String, &str)> = series.iter().enumerate()
+        .map(|(i, (name, _))| (name.to_string(), PALETTE[i % PALETTE.len()])).collect();
+    legend(&mut svg, &names);
+    svg.push_str("</svg>\n");
+    return svg;
+}
+
+// sizes of species stacked on top of each other, like the speciation graph from the NEAT paper
+fn species_chart(history:&[GenerationStats]) -> String {
+    let generations:Vec<f64> = history.iter().map(|stats| stats.generation as f64).collect();
+    let mut ids:Vec<usize> = history.iter().flat_map(|stats| stats.species.iter().map(|s| s.id)).collect();
+    ids.sort();
+    ids.dedup();
+
+    let totals:Vec<f64> = history.iter().map(|stats| stats.species.iter().map(|s| s.size as f64).sum()).collect();
+    let scale = Scale::new(&generations, &totals);
+    let mut svg = String::new();
+    writeln!(svg, "<h2>Species</h2>").unwrap();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">", CHART_WIDTH, CHART_HEIGHT).unwrap();
+    axes(&mut svg, &scale);
+
+    let mut lower = vec![0f64; history.len()];
+    for id in ids {
+        let upper:Vec<f64> = history.iter().zip(lower.iter())
+            .map(|(stats, bottom)| bottom + stats.species.iter().find(|s| s.id == id).map(|s| s.size as f64).unwrap_or(0f64))
+            .collect();
+        let mut points:Vec<String> = generations.iter().zip(upper.iter())
+            .map(|(x, y)| format!("{:.1},{:.1}", scale.x(*x), scale.y(*y))).collect();
+        points.extend(generations.iter().zip(lower.iter()).rev()
+            .map(|(x, y)| format!("{:.1},{:.1}", scale.x(*x), scale.y(*y))));
+        writeln!(svg, "<polygon fill=\"{}\" stroke=\"#fff\" stroke-width=\"0.5\" points=\"{}\"><title>specie {}</title></polygon>",
+            PALETTE[id % PALETTE.len()], points.join(" "), id).unwrap();
+        lower = upper;
+    }
+    svg.push_str("</svg>\n");
+    return svg;
+}
+
+pub fn render_report(history:&[GenerationStats]) -> String {
+    let mut html = String::from(HEADER);
+    if history.is_empty() {
+        html.push_str("<p>No generations were evaluated.</p>\n");
+    } else {
+        let generations:Vec<f64> = history.iter().map(|stats| stats.generation as f64).collect();
+        let series = |f:fn(&GenerationStats) -> f64| history.iter().map(f).collect::<Vec<f64>>();
+        html.push_str(&line_chart("Fitness", &generations, &[
+            ("best", series(|s| s.best_fitness)),
+            ("mean", series(|s| s.mean_fitness)),
+            ("median", series(|s| s.median_fitness)),
+            ("validation", series(|s| s.validation_fitness))
+        ]));
+        html.push_str(&line_chart("Complexity", &generations, &[
+            ("mean nodes", series(|s| s.mean_nodes)),
+            ("mean genes", series(|s| s.mean_genes))
+        ]));
+        html.push_str(&line_chart("Enabled genes", &generations, &[
+            ("enabled ratio", series(|s| s.enabled_ratio))
+        ]));
+        if history.iter().all(|stats| stats.mutation_rates.is_some()) {
+            let rates = |f:fn(&MutationRates) -> f64| history.iter().map(|s| f(s.mutation_rates.as_ref().unwrap())).collect::<Vec<f64>>();
+            html.push_str(&line_chart("Mutation rates", &generations, &[
+                ("weight mutation", rates(|r| r.weight_mutation_chance)),
+                ("weight step", rates(|r| r.weight_step)),
+                ("insert node", rates(|r| r.insert_node_chance)),
+                ("insert connection", rates(|r| r.insert_connection_chance))
+            ]));
+        }
+        html.push_str(&line_chart("Speciation", &generations, &[
+            ("species", series(|s| s.species_count as f64)),
+            ("threshold", series(|s| s.speciation_threshold))
+        ]));
+        html.push_str(&species_chart(history));
+    }
+    html.push_str(FOOTER);
+    return html;
+}
+
+// writes a self-contained html page with charts of the training progress
+pub fn write_report<P: AsRef<Path>>(history:&[GenerationStats], path:P) -> std::io::Result<()> {
+    return std::fs::write(path, render_report(history));
+}
@@ -0,0 +1,199 @@
+use super::structs::problem::Problem;
+use super::evaluation::{cross_entropy, Predictor};
+use super::generations::Dataset;
+use std::fmt;
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum Task {
+    Classification,
+    Regression
+}
+
+#[derive(Debug)]
+pub enum Report {
+    Classification(ClassificationReport),
+    Regression(RegressionReport)
+}
+
+#[derive(Debug)]
+pub struct ClassificationReport {
+    pub classes: Vec<String>,
+    // rows are actual classes, columns are predicted classes
+    pub confusion_matrix: Vec<Vec<usize>>,
+    pub accuracy: f64,
+    pub precision: Vec<f64>,
+    pub recall: Vec<f64>,
+    pub f1: Vec<f64>,
+    pub log_loss: f64
+}
+
+// class given by output values, a single output is a binary class with 0.5 threshold, 
+// otherwise it's the output with the highest value
+pub fn class_of(values:&[f64]) -> usize {
+    if values.len() == 1 {
+        return (values[0] >= 0.5) as usize;
+    }
+    let mut best = 0;
+    for (i, value) in values.iter().enumerate() {
+        if *value > values[best] {
+            best = i;
+        }
+    }
+    return best;
+}
+
+fn ratio(numerator:usize, denominator:usize) -> f64 {
+    if denominator == 0 {
+        return 0f64;
+    }
+    return numerator as f64 / denominator as f64;
+}
+
+// outputs are one-hot encoded classes, predicted class is the output node with the highest value,
+// a single output is the probability of the positive class of a binary problem
+pub fn classification_report(problem:&Problem, model:&mut dyn Predictor, dataset:&Dataset) -> ClassificationReport {
+    let binary = problem.outputs == 1;
+    let classes = if binary { 2 } else { problem.outputs };
+    let mut confusion_matrix = vec![vec![0usize; classes]; classes];
+    let mut log_loss = 0f64;
+
+    for (input, output) in dataset.inputs.iter().zip(dataset.outputs.iter()) {
+        let result = model.predict(input, problem);
+        let actual = class_of(output);
+        let predicted = class_of(&result);
+        confusion_matrix[actual][predicted] += 1;
+
+        let probabilities = if binary {
+            result
+        } else {
+            // output nodes are independent so they are normalised to get class probabilities
+            let sum:f64 = result.iter().sum();
+            result.iter().map(|x| if sum > 0f64 { x / sum } else { 1f64 / classes as f64 }).collect()
+        };
+        log_loss += cross_entropy(&probabilities, output, !binary);
+    }
+    log_loss /= dataset.len() as f64;
+
+    let correct:usize = (0..classes).map(|c| confusion_matrix[c][c]).sum();
+    let mut precision = Vec::new();
+    let mut recall = Vec::new();
+    let mut f1 = Vec::new();
+    for (c, row) in confusion_matrix.iter().enumerate() {
+        let predicted_count:usize = confusion_matrix.iter().map(|actual| actual[c]).sum();
+        let actual_count:usize = row.iter().sum();
+        let class_precision = ratio(row[c], predicted_count);
+        let class_recall = ratio(row[c], actual_count);
+        precision.push(class_precision);
+        recall.push(class_recall);
+        f1.push(if class_precision + class_recall > 0f64 {
+            2f64 * class_precision * class_recall / (class_precision + class_recall)
+        } else {
+            0f64
+        });
+    }
+
+    let class_names = if binary && dataset.output_names.len() == 1 {
+        vec![format!("{}=0", dataset.output_names[0]), format!("{}=1", dataset.output_names[0])]
+    } else if dataset.output_names.len() == classes {
+        dataset.output_names.clone()
+    } else {
+        (0..classes).map(|c| c.to_string()).collect()
+    };
+
+    return ClassificationReport {
+        classes: class_names,
+        accuracy: ratio(correct, dataset.len()),
+        confusion_matrix,
+        precision,
+        recall,
+        f1,
+        log_loss
+    };
+}
+
+impl fmt::Display for ClassificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "accuracy: {:.5}, \tlog-loss: {:.5}", self.accuracy, self.log_loss)?;
+        writeln!(f, "class\tprecision\trecall\tf1")?;
+        for (c, class) in self.classes.iter().enumerate() {
+            writeln!(f, "{}\t{:.5}\t\t{:.5}\t{:.5}", class, self.precision[c], self.recall[c], self.f1[c])?;
+        }
+        writeln!(f, "confusion matrix (rows: actual, columns: predicted)")?;
+        for (c, row) in self.confusion_matrix.iter().enumerate() {
+            let cells:Vec<String> = row.iter().map(|x| x.to_string()).collect();
+            writeln!(f, "{}\t{}", self.classes[c], cells.join("\t"))?;
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug)]
+pub struct RegressionReport {
+    pub mse: f64,
+    pub rmse: f64,
+    pub mae: f64,
+    // coefficient of determination averaged over outputs
+    pub r2: f64,
+    pub max_error: f64
+}
+
+pub fn regression_report(problem:&Problem, model:&mut dyn Predictor, dataset:&Dataset) -> RegressionReport {
+    let mut squared_error = 0f64;
+    let mut absolute_error = 0f64;
+    let mut max_error = 0f64;
+    let mut residual_sum = vec![0f64; problem.outputs];
+
+    for (input, output) in dataset.inputs.iter().zip(dataset.outputs.iter()) {
+        let result = model.predict(input, problem);
+        for (o, (value, expected)) in result.iter().zip(output).enumerate() {
+            let error = value - expected;
+            squared_error += error * error;
+            absolute_error += error.abs();
+            max_error = f64::max(max_error, error.abs());
+            residual_sum[o] += error * error;
+        }
+    }
+
+    let mut r2 = 0f64;
+    for (o, residual) in residual_sum.iter().enumerate() {
+        let mean = dataset.outputs.iter().map(|row| row[o]).sum::<f64>() / dataset.len() as f64;
+        let total:f64 = dataset.outputs.iter().map(|row| (row[o] - mean).powi(2)).sum();
+        // constant target is either predicted perfectly or not at all
+        r2 += if total > 0f64 { 1f64 - residual / total } else if *residual == 0f64 { 1f64 } else { 0f64 };
+    }
+
+    let count = (dataset.len() * problem.outputs) as f64;
+    let mse = squared_error / count;
+    return RegressionReport {
+        mse,
+        rmse: mse.sqrt(),
+        mae: absolute_error / count,
+        r2: r2 / problem.outputs as f64,
+        max_error
+    };
+}
+
+impl fmt::Display for RegressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return writeln!(f, "mse: {:.5}, \trmse: {:.5}, \tmae: {:.5}, \tr2: {:.5}, \tmax error: {:.5}", 
+            self.mse, self.rmse, self.mae, self.r2, self.max_error);
+    }
+}
+
+pub fn report(task:Task, problem:&Problem, model:&mut dyn Predictor, dataset:&Dataset) -> Report {
+    return match task {
+        Task::Classification => Report::Classification(classification_report(problem, model, dataset)),
+        Task::Regression => Report::Regression(regression_report(problem, model, dataset))
+    };
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Report::Classification(report) => report.fmt(f),
+            Report::Regression(report) => report.fmt(f)
+        };
+    }
+}
@@ -2,4 +2,15 @@ pub mod structs;
 pub mod evaluation;
 pub mod genetics;
 pub mod generations;
-pub mod visualisation;
\ No newline at end of file
+pub mod visualisation;
+pub mod cross_validation;
+pub mod metrics;
+pub mod history;
+pub mod statistics;
+pub mod reporting;
+pub mod termination;
+pub mod ensemble;
+pub mod backpropagation;
+pub mod weight_search;
+pub mod selection;
+pub mod restart;
\ No newline at end of file
@@ -0,0 +1,97 @@
+use super::structs::genome::Genome;
+use super::structs::problem::Problem;
+use super::structs::config::Config;
+use super::generations::{RunResult, Specie};
+use super::history::GenerationStats;
+use super::restart::Restart;
+use super::statistics::{LogLevel, StatisticsWriter};
+
+// observer of a run, all hooks do nothing by default so reporters implement only what they need
+pub trait Reporter {
+    fn run_start(&mut self, _problem:&Problem) {}
+    fn generation_start(&mut self, _generation:u64) {}
+    // called for each specie after its specimens are evaluated
+    fn specie_evaluated(&mut self, _generation:u64, _index:usize, _best:&Genome, _species_count:usize) {}
+    // called after specimens are evaluated, extinction generations are not evaluated
+    fn generation_evaluated(&mut self, _stats:&GenerationStats) {}
+    // best is the genome with the best validation fitness in this generation
+    fn generation_end(&mut self, _generation:u64, _best:&Genome, _species:&[Specie]) {}
+    fn specie_created(&mut self, _generation:u64, _id:usize) {}
+    fn specie_extinct(&mut self, _generation:u64, _id:usize) {}
+    fn new_best(&mut self, _generation:u64, _best:&Genome) {}
+    // population was rebuilt because training fitness stagnated
+    fn extinction(&mut self, _generation:u64, _restart:&Restart) {}
+    fn run_end(&mut self, _result:&RunResult) {}
+    // checked after every generation, run ends if any reporter returns true
+    fn should_stop(&mut self) -> bool {
+        return false;
+    }
+}
+
+// prints progress to stdout
+pub struct StdoutReporter {
+    log_level: LogLevel
+}
+
+impl StdoutReporter {
+    pub fn new(log_level:LogLevel) -> StdoutReporter {
+        return StdoutReporter { log_level };
+    }
+}
+
+impl Reporter for StdoutReporter {
+    fn run_start(&mut self, problem:&Problem) {
+        if self.log_level >= LogLevel::Generations {
+            println!("{:?}", problem);
+        }
+    }
+
+    fn specie_evaluated(&mut self, generation:u64, index:usize, best:&Genome, species_count:usize) {
+        if self.log_level >= LogLevel::Species {
+            println!("[{}/{}]\tbest fitness c: {:.5}, \tbest fitness: {:.5}, \tnodes: {:.5}, \tspecies: {:.5}",
+            generation, index,
+            best.fitness_complexity, best.validation_fitness,
+            best.active_nodes, species_count);
+        }
+    }
+
+    fn generation_end(&mut self, generation:u64, best:&Genome, species:&[Specie]) {
+        if self.log_level >= LogLevel::Generations {
+            println!(
+                "[{}]\tbest fitness c: {:.5}, \tbest fitness: {:.5}, \tnodes: {:.5}, \tspecies: {:.5}",
+                generation,
+                best.fitness_complexity, best.validation_fitness,
+                best.active_nodes, species.len());
+        }
+    }
+
+    fn extinction(&mut self, generation:u64, restart:&Restart) {
+        if self.log_level >= LogLevel::Generations {
+            println!("Noah get the boat");
+            println!("[{}]\t{}", generation, restart);
+        }
+    }
+
+    fn run_end(&mut self, result:&RunResult) {
+        if self.log_level >= LogLevel::Generations {
+            println!("[e]\tbest fitness c: {:.5}, \tbest fitness: {:.5}, \nnodes: {:.5}",
+                result.best.fitness_complexity, result.best.validation_fitness, result.best.active_nodes);
+            if let Some(reason) = &result.stop_reason {
+                println!("stopped: {}", reason);
+            }
+            print!("{}", result.report);
+        }
+    }
+}
+
+// stdout reporter and statistics writer chosen by the config
+pub fn default_reporters(config:&Config) -> Vec<Box<dyn Reporter>> {
+    let mut reporters:Vec<Box<dyn Reporter>> = vec![Box::new(StdoutReporter::new(config.log_level))];
+    if let Some(path) = &config.statistics_path {
+        match StatisticsWriter::new(path, config.statistics_format) {
+            Ok(writer) => reporters.push(Box::new(writer)),
+            Err(error) => println!("Couldn't create statistics file {:?}: {}", path, error)
+        }
+    }
+    return reporters;
+}
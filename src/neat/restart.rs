@@ -0,0 +1,105 @@
+use super::structs::genome::Genome;
+use std::fmt;
+use std::str::FromStr;
+
+// attempts to create a valid child before a copy of the first seed is used instead
+pub const RESTART_ATTEMPTS:usize = 100;
+// genomes kept in the hall of fame, more are kept if a strategy needs more seeds
+pub const HALL_OF_FAME_SIZE:usize = 10;
+
+// how the population is rebuilt when training fitness stagnates, genomes are taken
+// from the hall of fame of the best evaluated genomes of the run
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum RestartStrategy {
+    // population keeps evolving without restarts
+    None,
+    // children of the best k genomes crossed and mutated, each of them starts a specie
+    TopK(usize),
+    // weights of the genome with the best training fitness perturbed with gaussian noise
+    // of given sigma, topology is kept
+    DeltaCoding(f64),
+    // new random population joined by the best k genomes
+    RandomRestart(usize)
+}
+
+impl RestartStrategy {
+    // number of genomes taken from the hall of fame
+    pub fn seeds(&self) -> usize {
+        return match *self {
+            RestartStrategy::None => 0,
+            RestartStrategy::TopK(k) | RestartStrategy::RandomRestart(k) => k.max(1),
+            RestartStrategy::DeltaCoding(_) => 1
+        };
+    }
+}
+
+impl fmt::Display for RestartStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            RestartStrategy::None => write!(f, "none"),
+            RestartStrategy::TopK(k) => write!(f, "top:{}", k),
+            RestartStrategy::DeltaCoding(sigma) => write!(f, "delta:{}", sigma),
+            RestartStrategy::RandomRestart(k) => write!(f, "random:{}", k)
+        };
+    }
+}
+
+// "none", "top:<k>", "delta:<sigma>" or "random:<k>"
+impl FromStr for RestartStrategy {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (name, parameter) = match text.split_once(':') {
+            Some((name, parameter)) => (name, Some(parameter)),
+            None => (text, None)
+        };
+        let invalid = || format!("invalid restart strategy: {:?}", text);
+        return match (name, parameter) {
+            ("none", None) => Ok(RestartStrategy::None),
+            ("top", Some(k)) => Ok(RestartStrategy::TopK(k.parse().map_err(|_| invalid())?)),
+            ("delta", Some(sigma)) => Ok(RestartStrategy::DeltaCoding(sigma.parse().map_err(|_| invalid())?)),
+            ("random", Some(k)) => Ok(RestartStrategy::RandomRestart(k.parse().map_err(|_| invalid())?)),
+            _ => Err(invalid())
+        };
+    }
+}
+
+// summary of a restart passed to reporters
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Restart {
+    pub strategy: RestartStrategy,
+    // genomes the new population was built from
+    pub seeds: usize,
+    pub children: usize,
+    // invalid children that were created again
+    pub rejected: usize,
+    // children replaced by a copy of the first seed after RESTART_ATTEMPTS invalid ones
+    pub fallbacks: usize
+}
+
+impl fmt::Display for Restart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "restart: {}, \tseeds: {}, \tchildren: {}, \trejected: {}, \tfallbacks: {}",
+            self.strategy, self.seeds, self.children, self.rejected, self.fallbacks);
+    }
+}
+
+fn same_genes(a:&Genome, b:&Genome) -> bool {
+    return a.nodes == b.nodes && a.genes.len() == b.genes.len() && a.genes.iter().zip(b.genes.iter()).all(|(x, y)| {
+        x.input == y.input && x.output == y.output && x.weight == y.weight && x.enabled == y.enabled
+    });
+}
+
+// keeps `size` evaluated genomes with the best training fitness_complexity,
+// best first, genome that is already there isn't added again
+pub fn add_to_hall_of_fame(hall_of_fame:&mut Vec<Genome>, genome:&Genome, size:usize) {
+    if hall_of_fame.iter().any(|member| same_genes(member, genome)) {
+        return;
+    }
+    hall_of_fame.push(genome.clone());
+    hall_of_fame.sort_by(|a, b| {b.fitness_complexity.partial_cmp(&a.fitness_complexity).unwrap()});
+    hall_of_fame.truncate(size);
+}
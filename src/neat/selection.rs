@@ -0,0 +1,279 @@
+use super::structs::genome::Genome;
+use rand::{Rng, RngCore};
+use std::str::FromStr;
+
+// default number of contestants, the first specimen and 20 more drawn before
+// the tournament size was configurable
+pub const TOURNAMENT_SIZE:usize = 21;
+
+// fitness compared by selection, it's shared by the specie so big species don't take over
+fn fitness(genome:&Genome) -> f64 {
+    return genome.fitness_complexity_speciation;
+}
+
+// indices of specimens from the best to the worst
+fn ranking(specimens:&[Genome]) -> Vec<usize> {
+    let mut indices:Vec<usize> = (0..specimens.len()).collect();
+    indices.sort_by(|&a, &b| fitness(&specimens[b]).partial_cmp(&fitness(&specimens[a])).unwrap());
+    return indices;
+}
+
+// index of the slot containing the point, slots are consecutive ranges of given sizes
+fn slot(sizes:&[f64], point:f64) -> usize {
+    let mut end = 0f64;
+    for (index, size) in sizes.iter().enumerate() {
+        end += size;
+        if point < end {
+            return index;
+        }
+    }
+    return sizes.len() - 1;
+}
+
+// chooses parents from specimens of a single specie, specimens can't be empty
+pub trait Selection {
+    fn select<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome]) -> &'a Genome;
+
+    // parents for `count` selections, strategies choosing all of them at once override it
+    fn select_many<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome], count:usize) -> Vec<&'a Genome> {
+        return (0..count).map(|_| self.select(rng, specimens)).collect();
+    }
+}
+
+// the best of `size` specimens drawn with repetitions
+pub struct Tournament {
+    pub size: usize
+}
+
+impl Selection for Tournament {
+    fn select<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome]) -> &'a Genome {
+        let mut best = &specimens[rng.gen_range(0..specimens.len())];
+        for _ in 1..self.size {
+            let contestant = &specimens[rng.gen_range(0..specimens.len())];
+            if fitness(contestant) > fitness(best) {
+                best = contestant;
+            }
+        }
+        return best;
+    }
+}
+
+// chance of a specimen is proportional to its fitness
+pub struct Roulette;
+
+fn roulette_sizes(specimens:&[Genome]) -> Vec<f64> {
+    let sizes:Vec<f64> = specimens.iter().map(|specimen| fitness(specimen).max(0f64)).collect();
+    // all specimens have the same chance if none of them has positive fitness
+    if sizes.iter().sum::<f64>() <= 0f64 {
+        return vec![1f64; specimens.len()];
+    }
+    return sizes;
+}
+
+impl Selection for Roulette {
+    fn select<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome]) -> &'a Genome {
+        let sizes = roulette_sizes(specimens);
+        let total:f64 = sizes.iter().sum();
+        return &specimens[slot(&sizes, rng.gen_range(0f64..total))];
+    }
+}
+
+// linear ranking, the best specimen is `pressure` times more likely to be chosen
+// than an average one, pressure is between 1 (uniform) and 2
+pub struct Rank {
+    pub pressure: f64
+}
+
+impl Selection for Rank {
+    fn select<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome]) -> &'a Genome {
+        let ranking = ranking(specimens);
+        let n = specimens.len();
+        if n == 1 {
+            return &specimens[0];
+        }
+        // expected number of copies of the specimen at the given position of the ranking
+        let sizes:Vec<f64> = (0..n).map(|position| {
+            self.pressure - 2f64 * (self.pressure - 1f64) * position as f64 / (n - 1) as f64
+        }).collect();
+        let total:f64 = sizes.iter().sum();
+        return &specimens[ranking[slot(&sizes, rng.gen_range(0f64..total))]];
+    }
+}
+
+// uniform choice from the best `fraction` of specimens
+pub struct Truncation {
+    pub fraction: f64
+}
+
+impl Selection for Truncation {
+    fn select<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome]) -> &'a Genome {
+        let ranking = ranking(specimens);
+        let count = ((specimens.len() as f64 * self.fraction).ceil() as usize).clamp(1, specimens.len());
+        return &specimens[ranking[rng.gen_range(0..count)]];
+    }
+}
+
+// roulette with evenly spaced pointers, number of copies of each specimen
+// differs from the expected one by less than one
+pub struct StochasticUniversalSampling;
+
+impl Selection for StochasticUniversalSampling {
+    fn select<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome]) -> &'a Genome {
+        return self.select_many(rng, specimens, 1)[0];
+    }
+
+    fn select_many<'a>(&self, rng: &mut dyn RngCore, specimens: &'a [Genome], count:usize) -> Vec<&'a Genome> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let sizes = roulette_sizes(specimens);
+        let total:f64 = sizes.iter().sum();
+        let spacing = total / count as f64;
+        let start = rng.gen_range(0f64..spacing);
+        let mut selected = Vec::with_capacity(count);
+        let mut index = 0;
+        let mut end = sizes[0];
+        for pointer in (0..count).map(|i| start + i as f64 * spacing) {
+            while pointer >= end && index + 1 < sizes.len() {
+                index += 1;
+                end += sizes[index];
+            }
+            selected.push(&specimens[index]);
+        }
+        return selected;
+    }
+}
+
+// selection chosen in the config
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum SelectionMethod {
+    Tournament(usize),
+    Roulette,
+    // selection pressure
+    Rank(f64),
+    // fraction of the best specimens
+    Truncation(f64),
+    StochasticUniversalSampling
+}
+
+impl SelectionMethod {
+    pub fn selection(&self) -> Box<dyn Selection> {
+        return match *self {
+            SelectionMethod::Tournament(size) => Box::new(Tournament { size }),
+            SelectionMethod::Roulette => Box::new(Roulette),
+            SelectionMethod::Rank(pressure) => Box::new(Rank { pressure }),
+            SelectionMethod::Truncation(fraction) => Box::new(Truncation { fraction }),
+            SelectionMethod::StochasticUniversalSampling => Box::new(StochasticUniversalSampling)
+        };
+    }
+}
+
+// "tournament:<size>", "roulette", "rank:<pressure>", "truncation:<fraction>" or "sus"
+impl FromStr for SelectionMethod {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (name, parameter) = match text.split_once(':') {
+            Some((name, parameter)) => (name, Some(parameter)),
+            None => (text, None)
+        };
+        let invalid = || format!("invalid selection: {:?}", text);
+        return match (name, parameter) {
+            ("tournament", Some(size)) => Ok(SelectionMethod::Tournament(size.parse().map_err(|_| invalid())?)),
+            ("roulette", None) => Ok(SelectionMethod::Roulette),
+            ("rank", Some(pressure)) => Ok(SelectionMethod::Rank(pressure.parse().map_err(|_| invalid())?)),
+            ("truncation", Some(fraction)) => Ok(SelectionMethod::Truncation(fraction.parse().map_err(|_| invalid())?)),
+            ("sus", None) => Ok(SelectionMethod::StochasticUniversalSampling),
+            _ => Err(invalid())
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const DRAWS:usize = 10000;
+
+    fn specimens(fitness:&[f64]) -> Vec<Genome> {
+        return fitness.iter().map(|&value| Genome {
+            nodes: 0,
+            genes: Vec::new(),
+            active_nodes: 0,
+            fitness: value,
+            fitness_complexity: value,
+            fitness_complexity_speciation: value,
+            validation_fitness: 0f64,
+            mutation_rates: None
+        }).collect();
+    }
+
+    // the last specimen is the best one
+    fn fitness_values() -> Vec<f64> {
+        return (1..=10).map(|x| x as f64 / 10f64).collect();
+    }
+
+    // how many times the specimen with the highest fitness was selected
+    fn best_count(selection:&dyn Selection) -> usize {
+        let specimens = specimens(&fitness_values());
+        let mut rng = StdRng::seed_from_u64(0);
+        return (0..DRAWS).filter(|_| fitness(selection.select(&mut rng, &specimens)) == 1f64).count();
+    }
+
+    #[test]
+    fn bigger_tournament_selects_best_more_often() {
+        let small = best_count(&Tournament { size: 2 });
+        let big = best_count(&Tournament { size: 5 });
+        assert!(small < big, "{} >= {}", small, big);
+    }
+
+    #[test]
+    fn higher_rank_pressure_selects_best_more_often() {
+        let low = best_count(&Rank { pressure: 1.2 });
+        let high = best_count(&Rank { pressure: 1.9 });
+        assert!(low < high, "{} >= {}", low, high);
+    }
+
+    #[test]
+    fn smaller_truncation_selects_best_more_often() {
+        let wide = best_count(&Truncation { fraction: 0.5 });
+        let narrow = best_count(&Truncation { fraction: 0.2 });
+        assert!(wide < narrow, "{} >= {}", wide, narrow);
+    }
+
+    #[test]
+    fn stochastic_universal_sampling_matches_expected_counts() {
+        let fitness = fitness_values();
+        let specimens = specimens(&fitness);
+        let total:f64 = fitness.iter().sum();
+        let mut rng = StdRng::seed_from_u64(0);
+        for count in [7, 25, 100] {
+            let selected = StochasticUniversalSampling.select_many(&mut rng, &specimens, count);
+            assert_eq!(selected.len(), count);
+            for (specimen, value) in specimens.iter().zip(fitness.iter()) {
+                let actual = selected.iter().filter(|&&chosen| std::ptr::eq(chosen, specimen)).count();
+                let expected = count as f64 * value / total;
+                assert!((actual as f64 - expected).abs() < 1f64, "{} copies instead of {}", actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn roulette_without_positive_fitness_is_uniform() {
+        let specimens = specimens(&[0f64, 0f64, -1f64, 0f64]);
+        assert_eq!(roulette_sizes(&specimens), vec![1f64; 4]);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = [0usize; 4];
+        for _ in 0..DRAWS {
+            let chosen = Roulette.select(&mut rng, &specimens);
+            counts[specimens.iter().position(|specimen| std::ptr::eq(specimen, chosen)).unwrap()] += 1;
+        }
+        for count in counts {
+            assert!(count > DRAWS / 5, "{:?}", counts);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use super::history::GenerationStats;
+use super::reporting::Reporter;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// amount of progress information printed to stdout
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(PartialOrd)]
+pub enum LogLevel {
+    Silent,
+    // one line per generation and the final result
+    Generations,
+    // additionally one line per specie
+    Species
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum StatisticsFormat {
+    Csv,
+    JsonLines
+}
+
+// every record has all the columns, values that don't apply to the record type are left empty
+const COLUMNS: [&str; 17] = [
+    "type", "generation", "specie", "size", "best_fitness", "mean_fitness", "median_fitness",
+    "validation_fitness", "mean_nodes", "mean_genes", "enabled_ratio", "species_count", "speciation_threshold",
+    "weight_mutation_chance", "weight_step", "insert_node_chance", "insert_connection_chance"
+];
+
+// writes one record per generation followed by one record per specie of that generation
+pub struct StatisticsWriter {
+    format: StatisticsFormat,
+    writer: BufWriter<File>,
+    // set after the first error so it's reported only once
+    failed: bool
+}
+
+fn json_number(value:f64) -> String {
+    // json doesn't support infinities and NaN
+    if value.is_finite() {
+        return value.to_string();
+    }
+    return "null".to_string();
+}
+
+impl StatisticsWriter {
+    pub fn new<P: AsRef<Path>>(path:P, format:StatisticsFormat) -> std::io::Result<StatisticsWriter> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if let StatisticsFormat::Csv = format {
+            writeln!(writer, "{}", COLUMNS.join(","))?;
+        }
+        return Ok(StatisticsWriter { format, writer, failed: false });
+    }
+
+    fn write_record(&mut self, values:&[Option<String>]) -> std::io::Result<()> {
+        match self.format {
+            StatisticsFormat::Csv => {
+                let cells:Vec<String> = values.iter().map(|value| value.clone().unwrap_or_default()).collect();
+                writeln!(self.writer, "{}", cells.join(","))?;
+            }
+            StatisticsFormat::JsonLines => {
+                let fields:Vec<String> = COLUMNS.iter().zip(values.iter())
+                    .filter_map(|(column, value)| value.as_ref().map(|value| format!("\"{}\":{}", column, value)))
+                    .collect();
+                writeln!(self.writer, "{{{}}}", fields.join(","))?;
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn write(&mut self, stats:&GenerationStats) -> std::io::Result<()> {
+        let record_type = |name:&str| match self.format {
+            StatisticsFormat::Csv => name.to_string(),
+            StatisticsFormat::JsonLines => format!("\"{}\"", name)
+        };
+        let generation_type = record_type("generation");
+        let specie_type = record_type("specie");
+
+        self.write_record(&[
+            Some(generation_type),
+            Some(stats.generation.to_string()),
+            None,
+            Some(stats.species.iter().map(|s| s.size).sum::<usize>().to_string()),
+            Some(json_number(stats.best_fitness)),
+            Some(json_number(stats.mean_fitness)),
+            Some(json_number(stats.median_fitness)),
+            Some(json_number(stats.validation_fitness)),
+            Some(json_number(stats.mean_nodes)),
+            Some(json_number(stats.mean_genes)),
+            Some(json_number(stats.enabled_ratio)),
+            Some(stats.species_count.to_string()),
+            Some(json_number(stats.speciation_threshold)),
+            stats.mutation_rates.map(|rates| json_number(rates.weight_mutation_chance)),
+            stats.mutation_rates.map(|rates| json_number(rates.weight_step)),
+            stats.mutation_rates.map(|rates| json_number(rates.insert_node_chance)),
+            stats.mutation_rates.map(|rates| json_number(rates.insert_connection_chance))
+        ])?;
+        for specie in stats.species.iter() {
+            self.write_record(&[
+                Some(specie_type.clone()),
+                Some(stats.generation.to_string()),
+                Some(specie.id.to_string()),
+                Some(specie.size.to_string()),
+                Some(json_number(specie.best_fitness)),
+                None, None, None, None, None, None, None, None, None, None, None, None
+            ])?;
+        }
+        return self.writer.flush();
+    }
+}
+
+impl Reporter for StatisticsWriter {
+    fn generation_evaluated(&mut self, stats:&GenerationStats) {
+        if self.failed {
+            return;
+        }
+        if let Err(error) = self.write(stats) {
+            println!("Couldn't write statistics: {}", error);
+            self.failed = true;
+        }
+    }
+}
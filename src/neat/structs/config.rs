@@ -0,0 +1,148 @@
+use super::super::evaluation::{OutputActivation, FitnessFunction};
+use super::super::metrics::Task;
+use super::super::ensemble::Combination;
+use super::super::backpropagation::FineTuning;
+use super::super::weight_search::WeightSearch;
+use super::super::selection::{SelectionMethod, TOURNAMENT_SIZE};
+use super::super::restart::RestartStrategy;
+use super::super::genetics::{WeightDistribution, WeightPerturbation};
+use super::super::statistics::{LogLevel, StatisticsFormat};
+use super::super::termination::StopCondition;
+
+// parameters of a single run that can be changed without recompiling 
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Config {
+    // seed of the random generator, runs with the same seed and config give the same results
+    pub seed: Option<u64>,
+    // number of training rows evaluated in each generation, 
+    // rows are drawn at random once per generation and shared by all specimens,
+    // whole training dataset is used if it's None
+    pub batch_size: Option<usize>,
+    // evaluate best specimen of each specie on the whole training dataset 
+    // after the mini-batch evaluation 
+    pub reevaluate_elites: bool,
+    // fractions of rows held out from training, validation dataset chooses the best genome
+    // and test dataset gives its final score, 
+    // training rows are used for validation if validation part is zero 
+    pub validation_part: f64,
+    pub test_part: f64,
+    // activation of output nodes, use Identity or Scaled for regression
+    pub output_activation: OutputActivation,
+    // Softmax outputs work best with CrossEntropy
+    pub fitness_function: FitnessFunction,
+    // decides which metrics are reported for the best genome
+    pub task: Task,
+    // how parents are chosen from specimens of a specie
+    pub selection: SelectionMethod,
+    // part of children which are mutated clones of a single parent instead of crossed parents,
+    // clones get only structural mutations, their self-adaptive rates are still adapted
+    pub mutation_only_part: f64,
+    // chance that the second parent of a crossed child is chosen from a random other specie
+    pub interspecies_mating_chance: f64,
+    // maximal difference between a genome and specie representative 
+    // for the genome to be assigned to the specie
+    pub speciation_threshold: f64,
+    // weight of connections of start genomes, new connections and bias of new nodes,
+    // also used when a weight is replaced
+    pub weight_init: WeightDistribution,
+    // chance that weight of each gene of a mutated genome is changed
+    pub weight_mutation_chance: f64,
+    // chances that a mutated genome gets a new node splitting one of its connections 
+    // (per connection) or a new connection (per genome)
+    pub insert_node_chance: f64,
+    pub insert_connection_chance: f64,
+    // every genome carries its own mutation rates which are mutated with it,
+    // initial values are the rates above
+    pub self_adaptive_mutation: bool,
+    // scale of log-normal changes of self-adaptive rates
+    pub adaptation_rate: f64,
+    pub weight_perturbation: WeightPerturbation,
+    // chance that a changed weight is drawn again from `weight_init` instead of being perturbed
+    pub weight_replace_chance: f64,
+    // weights are clamped to this range after every change
+    pub min_weight: f64,
+    pub max_weight: f64,
+    // weights of elites or the final champion trained with backpropagation, no training if it's None
+    pub fine_tuning: Option<FineTuning>,
+    // weights of the final champion searched without gradients, 
+    // after fine-tuning if both are set, no search if it's None
+    pub weight_search: Option<WeightSearch>,
+    // chances that a mutated genome loses one connection or one hidden node 
+    // with its connections, they let networks shrink during the run, disabled by default
+    pub delete_connection_chance: f64,
+    pub delete_node_chance: f64,
+    // chance that a mutated genome enables one of its disabled genes
+    pub reenable_chance: f64,
+    // disabled genes are removed this many generations after they were disabled,
+    // they are kept forever if it's None
+    pub disabled_gene_lifetime: Option<u64>,
+    // how the population is rebuilt after training fitness stagnates
+    pub restart: RestartStrategy,
+    pub log_level: LogLevel,
+    // file receiving a record for every generation and specie, nothing is saved if it's None
+    pub statistics_path: Option<String>,
+    pub statistics_format: StatisticsFormat,
+    // run stops when any of the conditions is met, 
+    // without conditions it runs until a reporter stops it
+    pub stop_conditions: Vec<StopCondition>,
+    // number of species whose champions form an ensemble returned with the best genome,
+    // no ensemble is created if it's None
+    pub ensemble_size: Option<usize>,
+    pub ensemble_combination: Combination
+}
+
+impl Config {
+    // parameters that can't be checked by their types,
+    // weights are clamped to the range after every change, so it can't be empty
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_weight.is_nan() || self.max_weight.is_nan() || self.min_weight > self.max_weight {
+            return Err(format!("min_weight {} is greater than max_weight {}", self.min_weight, self.max_weight));
+        }
+        return Ok(());
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            seed: None,
+            batch_size: None,
+            reevaluate_elites: true,
+            validation_part: 0.2,
+            test_part: 0.2,
+            output_activation: OutputActivation::Sigmoid,
+            fitness_function: FitnessFunction::MeanAbsoluteError,
+            task: Task::Classification,
+            selection: SelectionMethod::Tournament(TOURNAMENT_SIZE),
+            mutation_only_part: 0.25,
+            interspecies_mating_chance: 0.001,
+            speciation_threshold: 1.0,
+            // like bias connections of new nodes before it was configurable,
+            // so new connections take part in the evaluation right away
+            weight_init: WeightDistribution::Uniform { min: -0.1, max: 0.1 },
+            weight_mutation_chance: 0.15,
+            insert_node_chance: 0.05,
+            insert_connection_chance: 0.05,
+            self_adaptive_mutation: false,
+            adaptation_rate: 0.2,
+            weight_perturbation: WeightPerturbation::Uniform(0.25),
+            weight_replace_chance: 0.0,
+            min_weight: f64::NEG_INFINITY,
+            max_weight: f64::INFINITY,
+            fine_tuning: None,
+            weight_search: None,
+            delete_connection_chance: 0.0,
+            delete_node_chance: 0.0,
+            reenable_chance: 0.0,
+            disabled_gene_lifetime: None,
+            restart: RestartStrategy::TopK(2),
+            log_level: LogLevel::Species,
+            statistics_path: None,
+            statistics_format: StatisticsFormat::Csv,
+            stop_conditions: vec![StopCondition::MaxGenerations(10)],
+            ensemble_size: None,
+            ensemble_combination: Combination::Average
+        }
+    }
+}
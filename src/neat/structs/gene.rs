@@ -6,17 +6,20 @@ pub struct Gene {
     pub output: usize,
     pub weight: f64,
     pub enabled: bool,
-    pub innovation: u64
+    pub innovation: u64,
+    // generation the gene was disabled in, it isn't saved with the genome
+    pub disabled_at: Option<u64>
 }
 
 impl Gene {
-    pub fn new(input: usize, output: usize) -> Gene {
+    pub fn new(input: usize, output: usize, weight: f64) -> Gene {
         return Gene{
-            input: input, 
-            output: output, 
-            weight: 1f64,
+            input, 
+            output, 
+            weight,
             enabled: true,
-            innovation: 0
+            innovation: 0,
+            disabled_at: None
         };
     }
 }
\ No newline at end of file
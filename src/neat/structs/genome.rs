@@ -1,5 +1,11 @@
 use super::gene::Gene;
+use super::mutation_rates::MutationRates;
+use super::problem::{load_model, save_model, Problem};
 use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Debug)]
 pub struct Genome {
@@ -9,7 +15,9 @@ pub struct Genome {
     pub fitness: f64,
     pub fitness_complexity: f64,
     pub fitness_complexity_speciation: f64,
-    pub validation_fitness: f64
+    pub validation_fitness: f64,
+    // own mutation rates if mutation is self-adaptive, they aren't saved with the genome
+    pub mutation_rates: Option<MutationRates>
 }
 
 impl Clone for Genome {
@@ -17,7 +25,8 @@ impl Clone for Genome {
         Genome { nodes: self.nodes, genes: self.genes.clone(), 
             fitness_complexity_speciation: self.fitness_complexity_speciation, fitness: self.fitness,
             fitness_complexity: self.fitness_complexity,
-            active_nodes:self.active_nodes, validation_fitness:self.validation_fitness }
+            active_nodes:self.active_nodes, validation_fitness:self.validation_fitness,
+            mutation_rates: self.mutation_rates }
     }
 }
 
@@ -31,22 +40,18 @@ impl Genome {
         }
         return count;
     }
+    // whether there is a gene from input to output node, enabled or not
     pub fn has_connection(&self, input: usize, output: usize) -> bool {
-        for gene in self.genes.iter() {
-            if (gene.input == input && gene.output == output) 
-                && (gene.input == output && gene.output == input) {
-                    return true;
-            }
-        }
-        return false;
+        return self.genes.iter().any(|gene| gene.input == input && gene.output == output);
     }
 
     fn has_cycles_recursive(&self, vertex:usize, visited:&mut HashSet<usize>, recursion_stack:&mut HashSet<usize>) -> bool {
         visited.insert(vertex);
         recursion_stack.insert(vertex);
 
+        // disabled genes aren't evaluated so they can't form cycles
         for gene in self.genes.iter() {
-            if gene.input == vertex {
+            if gene.enabled && gene.input == vertex {
                 let neighbor = gene.output;
                 if !visited.contains(&neighbor) {
                     if self.has_cycles_recursive(neighbor, visited, recursion_stack) {
@@ -62,18 +67,109 @@ impl Genome {
         return false;
     }
 
+    fn node_depth(&self, node:usize, depths:&mut Vec<Option<usize>>, visiting:&mut HashSet<usize>) -> usize {
+        if let Some(depth) = depths[node] {
+            return depth;
+        }
+        // guards against infinite recursion, valid genomes don't contain cycles 
+        visiting.insert(node);
+        let mut depth = 0;
+        for gene in self.genes.iter() {
+            if gene.enabled && gene.output == node && !visiting.contains(&gene.input) {
+                depth = usize::max(depth, self.node_depth(gene.input, depths, visiting) + 1);
+            }
+        }
+        visiting.remove(&node);
+        depths[node] = Some(depth);
+        return depth;
+    }
+
+    // length of the longest path of enabled connections leading to each node,
+    // nodes without incoming connections have depth 0
+    pub fn node_depths(&self) -> Vec<usize> {
+        let mut depths = vec![None; self.nodes];
+        let mut visiting = HashSet::new();
+        for node in 0..self.nodes {
+            self.node_depth(node, &mut depths, &mut visiting);
+        }
+        return depths.into_iter().map(|depth| depth.unwrap()).collect();
+    }
+
     pub fn has_cycles(&self) -> bool {
         let mut visited: HashSet<usize> = HashSet::new();
         let mut recursion_stack: HashSet<usize> = HashSet::new();
 
         for node in 0..self.nodes {
-            if !visited.contains(&node) {
-                if self.has_cycles_recursive(node, &mut visited, &mut recursion_stack) {
-                    return true;
-                }
+            if !visited.contains(&node) 
+                && self.has_cycles_recursive(node, &mut visited, &mut recursion_stack) {
+                return true;
             }
         }
         
         return false;
     }
 }
+
+// text format: "nodes <count>" line followed by one 
+// "<input> <output> <weight> <enabled> <innovation>" line per gene
+impl fmt::Display for Genome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "nodes {}", self.nodes)?;
+        for gene in self.genes.iter() {
+            writeln!(f, "{} {} {} {} {}", gene.input, gene.output, gene.weight, gene.enabled, gene.innovation)?;
+        }
+        return Ok(());
+    }
+}
+
+impl FromStr for Genome {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let nodes = match lines.next().map(|line| line.split_whitespace().collect::<Vec<_>>()) {
+            Some(header) if header.len() == 2 && header[0] == "nodes" => 
+                header[1].parse::<usize>().map_err(|e| e.to_string())?,
+            _ => return Err("expected \"nodes <count>\" header".to_string())
+        };
+        let mut genes = Vec::new();
+        for line in lines {
+            let fields:Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 5 {
+                return Err(format!("invalid gene: {:?}", line));
+            }
+            let gene = Gene {
+                input: fields[0].parse().map_err(|_| format!("invalid gene input: {:?}", line))?,
+                output: fields[1].parse().map_err(|_| format!("invalid gene output: {:?}", line))?,
+                weight: fields[2].parse().map_err(|_| format!("invalid gene weight: {:?}", line))?,
+                enabled: fields[3].parse().map_err(|_| format!("invalid gene enabled flag: {:?}", line))?,
+                innovation: fields[4].parse().map_err(|_| format!("invalid gene innovation: {:?}", line))?,
+                disabled_at: None
+            };
+            if gene.input >= nodes || gene.output >= nodes {
+                return Err(format!("gene refers to a node out of range: {:?}", line));
+            }
+            genes.push(gene);
+        }
+        return Ok(Genome {
+            nodes,
+            genes,
+            active_nodes: 0,
+            fitness: 0f64,
+            fitness_complexity: 0f64,
+            fitness_complexity_speciation: 0f64,
+            validation_fitness: 0f64,
+            mutation_rates: None
+        });
+    }
+}
+
+impl Genome {
+    pub fn save<P: AsRef<Path>>(&self, problem:&Problem, path:P) -> std::io::Result<()> {
+        return save_model(self, problem, path);
+    }
+
+    pub fn load<P: AsRef<Path>>(path:P) -> Result<(Genome, Problem), Box<dyn Error>> {
+        return load_model(path);
+    }
+}
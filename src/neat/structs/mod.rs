@@ -1,3 +1,5 @@
 pub mod gene;
 pub mod genome;
-pub mod problem;
\ No newline at end of file
+pub mod problem;
+pub mod config;
+pub mod mutation_rates;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use super::config::Config;
+
+// mutation parameters carried by a genome when mutation is self-adaptive,
+// they are mutated before the genome so good values spread with good genomes
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub struct MutationRates {
+    pub weight_mutation_chance: f64,
+    // multiplies changes drawn from `Config::weight_perturbation`
+    pub weight_step: f64,
+    pub insert_node_chance: f64,
+    pub insert_connection_chance: f64
+}
+
+impl MutationRates {
+    // initial rates are the global ones
+    pub fn from_config(config:&Config) -> MutationRates {
+        return MutationRates {
+            weight_mutation_chance: config.weight_mutation_chance,
+            weight_step: 1f64,
+            insert_node_chance: config.insert_node_chance,
+            insert_connection_chance: config.insert_connection_chance
+        };
+    }
+
+    // arithmetic mean of all rates, None if there are no rates
+    pub fn mean<'a, I: Iterator<Item = &'a MutationRates>>(rates:I) -> Option<MutationRates> {
+        let mut sum = MutationRates { weight_mutation_chance: 0f64, weight_step: 0f64, insert_node_chance: 0f64, insert_connection_chance: 0f64 };
+        let mut count = 0;
+        for rate in rates {
+            sum.weight_mutation_chance += rate.weight_mutation_chance;
+            sum.weight_step += rate.weight_step;
+            sum.insert_node_chance += rate.insert_node_chance;
+            sum.insert_connection_chance += rate.insert_connection_chance;
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+        let count = count as f64;
+        return Some(MutationRates {
+            weight_mutation_chance: sum.weight_mutation_chance / count,
+            weight_step: sum.weight_step / count,
+            insert_node_chance: sum.insert_node_chance / count,
+            insert_connection_chance: sum.insert_connection_chance / count
+        });
+    }
+}
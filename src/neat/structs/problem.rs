@@ -1,5 +1,50 @@
+use super::super::evaluation::{OutputActivation, FitnessFunction};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
 #[derive(Debug)]
+#[derive(Clone)]
 pub struct Problem {
     pub inputs: usize,
-    pub outputs: usize
-}
\ No newline at end of file
+    pub outputs: usize,
+    pub output_activation: OutputActivation,
+    pub fitness_function: FitnessFunction
+}
+
+// text format: "problem <inputs> <outputs> <output activation> <fitness function>" line
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return writeln!(f, "problem {} {} {} {}", self.inputs, self.outputs, self.output_activation, self.fitness_function);
+    }
+}
+
+impl FromStr for Problem {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let fields:Vec<&str> = text.split_whitespace().collect();
+        if fields.len() != 5 || fields[0] != "problem" {
+            return Err("expected \"problem <inputs> <outputs> <output activation> <fitness function>\" header".to_string());
+        }
+        return Ok(Problem {
+            inputs: fields[1].parse().map_err(|_| format!("invalid number of inputs: {:?}", text))?,
+            outputs: fields[2].parse().map_err(|_| format!("invalid number of outputs: {:?}", text))?,
+            output_activation: fields[3].parse()?,
+            fitness_function: fields[4].parse()?
+        });
+    }
+}
+
+// saved genomes and ensembles start with the problem they were trained on,
+// so they are evaluated with the same output activation
+pub fn save_model<M: fmt::Display, P: AsRef<Path>>(model:&M, problem:&Problem, path:P) -> std::io::Result<()> {
+    return std::fs::write(path, format!("{}{}", problem, model));
+}
+
+pub fn load_model<M: FromStr<Err = String>, P: AsRef<Path>>(path:P) -> Result<(M, Problem), Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    let (header, model) = text.split_once('\n').unwrap_or((&text, ""));
+    return Ok((model.parse::<M>()?, header.parse::<Problem>()?));
+}
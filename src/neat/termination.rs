@@ -0,0 +1,67 @@
+use std::fmt;
+use std::time::Duration;
+
+// condition ending the run, run stops when any of the configured conditions is met
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum StopCondition {
+    MaxGenerations(u64),
+    // best training fitness of the last generation or best validation fitness reaches the value
+    FitnessThreshold { fitness: f64, validation: bool },
+    // wall-clock time since the first generation
+    TimeBudget(Duration),
+    // number of genome evaluations on the training dataset, an epoch of backpropagation counts as one
+    MaxEvaluations(u64),
+    // number of generations without improvement of the best validation fitness,
+    // patience of early stopping
+    Stagnation(u64)
+}
+
+// state of the run checked against stop conditions
+pub struct Progress {
+    pub generation: u64,
+    pub training_fitness: f64,
+    pub validation_fitness: f64,
+    pub elapsed: Duration,
+    pub evaluations: u64,
+    pub generations_without_improvement: u64
+}
+
+impl StopCondition {
+    pub fn is_met(&self, progress:&Progress) -> bool {
+        return match self {
+            StopCondition::MaxGenerations(generations) => progress.generation >= *generations,
+            StopCondition::FitnessThreshold { fitness, validation: true } => progress.validation_fitness >= *fitness,
+            StopCondition::FitnessThreshold { fitness, validation: false } => progress.training_fitness >= *fitness,
+            StopCondition::TimeBudget(budget) => progress.elapsed >= *budget,
+            StopCondition::MaxEvaluations(evaluations) => progress.evaluations >= *evaluations,
+            StopCondition::Stagnation(generations) => progress.generations_without_improvement >= *generations
+        };
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum StopReason {
+    Condition(StopCondition),
+    // one of the reporters requested to stop
+    Reporter
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            StopReason::Condition(StopCondition::MaxGenerations(generations)) =>
+                write!(f, "reached {} generations", generations),
+            StopReason::Condition(StopCondition::FitnessThreshold { fitness, validation }) =>
+                write!(f, "{} fitness reached {}", if *validation { "validation" } else { "training" }, fitness),
+            StopReason::Condition(StopCondition::TimeBudget(budget)) =>
+                write!(f, "time budget of {:?} exceeded", budget),
+            StopReason::Condition(StopCondition::MaxEvaluations(evaluations)) =>
+                write!(f, "reached {} evaluations", evaluations),
+            StopReason::Condition(StopCondition::Stagnation(generations)) =>
+                write!(f, "no improvement for {} generations", generations),
+            StopReason::Reporter => write!(f, "stopped by a reporter")
+        };
+    }
+}
@@ -1,127 +1,254 @@
 use super::structs::genome::Genome;
 use super::structs::problem::Problem;
-use std::{
-    fs::File,
-    io::Write,
-};
+use super::generations::Dataset;
+use std::fmt::Write;
+use std::path::Path;
 
-const HEADER: &'static str = "
-<!DOCTYPE html>
+// network is rendered to inline svg, so the file doesn't need any external scripts
+const HEADER: &str = "<!DOCTYPE html>
 <html lang=\"en\">
 <head>
     <meta charset=\"UTF-8\">
-    <meta http-equiv=\"X-UA-Compatible\" content=\"IE=edge\">
-    <script src=\"https://cdnjs.cloudflare.com/ajax/libs/cytoscape/3.21.2/cytoscape.min.js\" integrity=\"sha512-I1SEbsEt/GKuL+DudstOv/Ain+H/7IO3J2VHprg9IQOIIRmNQTC2WMdPXrHmYF9Qo1jRGnMrHtY7NqP9oaKFAQ==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>
-    <title>Document</title>
+    <title>Network</title>
+    <style>
+        .node { fill: #666; }
+        .label { font: 12px sans-serif; fill: #000; text-anchor: middle; }
+        .edge { fill: none; }
+        .disabled { stroke: #ccc; stroke-dasharray: 6 4; }
+        .weight { font: 10px sans-serif; fill: #444; text-anchor: middle; }
+    </style>
 </head>
 <body>
-<div id=\"cy\" style=\"width: 800px;
-height: 600px;
-display: block;\"></div>
-  <script>
-  var cy = cytoscape({
-
-container: document.getElementById('cy'), // container to render in
+";
 
-elements: [ // list of graph elements to start with
+const FOOTER: &str = "</body>
+</html>
 ";
 
-const FOOTER: &'static str = "
-],
+const NODE_RADIUS: f64 = 15.0;
+const ROW_HEIGHT: f64 = 100.0;
+const COLUMN_WIDTH: f64 = 250.0;
+const MARGIN: f64 = 50.0;
+const MAX_EDGE_WIDTH: f64 = 6.0;
+const POSITIVE_COLOR: &str = "#2b6cb0";
+const NEGATIVE_COLOR: &str = "#c53030";
+// number of barycenter passes used to reduce edge crossings
+const ORDERING_SWEEPS: usize = 4;
 
-layout: {
-  name: 'preset'
-},
+// nodes grouped into columns, inputs are in the first column and outputs in the last one
+pub struct Layout {
+    pub layers: Vec<Vec<usize>>,
+    // (x, y) of every node
+    pub positions: Vec<(f64, f64)>,
+    pub width: f64,
+    pub height: f64
+}
 
-style: [ // the stylesheet for the graph
-  {
-    selector: 'node',
-    style: {
-      'background-color': '#666',
-      'label': 'data(id)'
+// average position of already ordered predecessors (or successors) of the node
+fn barycenter(genome:&Genome, node:usize, order:&[Option<f64>], incoming:bool) -> Option<f64> {
+    let mut sum = 0f64;
+    let mut count = 0;
+    for gene in genome.genes.iter().filter(|gene| gene.enabled) {
+        let neighbour = if incoming && gene.output == node {
+            gene.input
+        } else if !incoming && gene.input == node {
+            gene.output
+        } else {
+            continue;
+        };
+        if let Some(position) = order[neighbour] {
+            sum += position;
+            count += 1;
+        }
     }
-  },
-
-  {
-    selector: 'edge',
-    style: {
-      'width': 3,
-      'line-color': '#ccc',
-      'target-arrow-color': '#ccc',
-      'target-arrow-shape': 'triangle',
-      'curve-style': 'bezier'
+    if count == 0 {
+        return None;
     }
-  }
-],
+    return Some(sum / count as f64);
+}
 
-});
-  </script>
-</body>
-</html>
-";
+fn sort_layer(genome:&Genome, layer:&mut [usize], order:&[Option<f64>], incoming:bool) {
+    let mut sorted:Vec<(usize, f64)> = layer.iter().enumerate()
+        .map(|(i, &node)| (node, barycenter(genome, node, order, incoming).unwrap_or(i as f64)))
+        .collect();
+    sorted.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    for (i, (node, _)) in sorted.into_iter().enumerate() {
+        layer[i] = node;
+    }
+}
 
-pub fn visualise(genome:&Genome, problem:&Problem) {
-    let mut file = File::create("out.html").unwrap();
-    file.write_all(HEADER.as_bytes()).unwrap();
+// hidden nodes are placed in layers by the longest path from the inputs, 
+// then each layer is ordered by barycenters of connected nodes to reduce edge crossings
+pub fn layout(genome:&Genome, problem:&Problem) -> Layout {
+    let outputs_start = problem.inputs;
+    let hidden_start = problem.inputs + problem.outputs;
+    let depths = genome.node_depths();
 
-    for n in 0..genome.nodes {
-        if n < problem.inputs {
-          write!(&mut file, "
-          {{ 
-            data: {{ 
-              id: '{0}', 
-               
-          }}, position: {{ 
-            y: {0}*100,
-            x: -400 
-          }},
-          style: {{ 
-            'label': '[in {0}]'
-          }}
-          }},\n", n).unwrap();
+    let hidden_layers = (hidden_start..genome.nodes).map(|n| usize::max(depths[n], 1)).max().unwrap_or(0);
+    let mut layers:Vec<Vec<usize>> = vec![Vec::new(); hidden_layers + 2];
+    layers[0] = (0..outputs_start).collect();
+    for n in hidden_start..genome.nodes {
+        layers[usize::max(depths[n], 1)].push(n);
+    }
+    layers[hidden_layers + 1] = (outputs_start..hidden_start).collect();
+
+    // inputs and outputs keep their order so they can be matched with dataset columns
+    for sweep in 0..ORDERING_SWEEPS {
+        let incoming = sweep % 2 == 0;
+        let mut order = vec![None; genome.nodes];
+        let mut indices:Vec<usize> = (1..layers.len() - 1).collect();
+        if !incoming {
+            indices.reverse();
         }
-        else if n >= problem.inputs && n < problem.inputs + problem.outputs {
-          write!(&mut file, "
-          {{ 
-            data: {{ 
-              id: '{0}'
-          }}, 
-          position: {{  
-          y: {1}*100,
-          x: 400 
-        }}, 
-          style: {{ 
-            'label': '[out {0}]'
-          }}
-          }},\n", n, n - problem.inputs ).unwrap();
+        for layer in layers.iter() {
+            for (i, &node) in layer.iter().enumerate() {
+                order[node] = Some(i as f64);
+            }
         }
-        else {
-          write!(&mut file, "
-          {{ 
-            data: {{ 
-              id: '{0}'
-          }}, 
-          position: {{ 
-            y: {1}*100,
-            x: 0 
-          }}, 
-          style: {{ 
-            'label': '[{0}]'
-          }}
-          }},\n", n, n - (problem.inputs + problem.outputs) + 1).unwrap();
+        for index in indices {
+            sort_layer(genome, &mut layers[index], &order, incoming);
+            for (i, &node) in layers[index].iter().enumerate() {
+                order[node] = Some(i as f64);
+            }
         }
     }
+
+    let rows = layers.iter().map(|layer| layer.len()).max().unwrap_or(1);
+    let height = 2.0 * MARGIN + rows.saturating_sub(1) as f64 * ROW_HEIGHT;
+    let width = 2.0 * MARGIN + (layers.len() - 1) as f64 * COLUMN_WIDTH;
+    let mut positions = vec![(0f64, 0f64); genome.nodes];
+    for (column, layer) in layers.iter().enumerate() {
+        // center shorter layers vertically
+        let offset = (rows - layer.len()) as f64 * ROW_HEIGHT / 2.0;
+        for (row, &node) in layer.iter().enumerate() {
+            positions[node] = (MARGIN + column as f64 * COLUMN_WIDTH, MARGIN + offset + row as f64 * ROW_HEIGHT);
+        }
+    }
+
+    return Layout { layers, positions, width, height };
+}
+
+fn node_label(n:usize, problem:&Problem) -> String {
+    if n < problem.inputs {
+        return format!("[in {}]", n);
+    } else if n < problem.inputs + problem.outputs {
+        return format!("[out {}]", n);
+    } else {
+        return format!("[{}]", n);
+    }
+}
+
+// disabled genes are drawn as dashed gray lines if `show_disabled` is set
+pub fn render_svg(genome:&Genome, problem:&Problem, show_disabled:bool) -> String {
+    let layout = layout(genome, problem);
+
+    let mut svg = String::new();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        layout.width, layout.height, layout.width, layout.height).unwrap();
+    writeln!(svg, "<defs>").unwrap();
+    for (id, color) in [("positive", POSITIVE_COLOR), ("negative", NEGATIVE_COLOR), ("disabled", "#ccc")] {
+        writeln!(svg, "<marker id=\"arrow-{}\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"10\" markerHeight=\"10\" \
+            markerUnits=\"userSpaceOnUse\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\"/></marker>", id, color).unwrap();
+    }
+    writeln!(svg, "</defs>").unwrap();
+
+    let max_weight = genome.genes.iter().filter(|gene| gene.enabled)
+        .map(|gene| gene.weight.abs()).fold(0f64, f64::max);
+
+    for gene in genome.genes.iter() {
+        if !gene.enabled && !show_disabled {
+            continue;
+        }
+        let (x1, y1) = layout.positions[gene.input];
+        let (x2, y2) = layout.positions[gene.output];
+        // shorten the line so the arrow ends at the edge of the target node
+        let length = f64::max(f64::hypot(x2 - x1, y2 - y1), 1.0);
+        let (end_x, end_y) = (x2 - (x2 - x1) * NODE_RADIUS / length, y2 - (y2 - y1) * NODE_RADIUS / length);
+        let style = if !gene.enabled {
+            "class=\"edge disabled\" stroke-width=\"1\" marker-end=\"url(#arrow-disabled)\"".to_string()
+        } else {
+            // color shows the sign of the weight and width shows its magnitude
+            let (color, marker) = if gene.weight >= 0f64 { (POSITIVE_COLOR, "positive") } else { (NEGATIVE_COLOR, "negative") };
+            let width = if max_weight > 0f64 { 1.0 + (MAX_EDGE_WIDTH - 1.0) * gene.weight.abs() / max_weight } else { 1.0 };
+            format!("class=\"edge\" stroke=\"{}\" stroke-width=\"{:.2}\" marker-end=\"url(#arrow-{})\"", color, width, marker)
+        };
+        writeln!(svg, "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" {}/>",
+            x1, y1, end_x, end_y, style).unwrap();
+        writeln!(svg, "<text class=\"weight\" x=\"{:.1}\" y=\"{:.1}\">{:.2}</text>",
+            (x1 + x2) / 2.0, (y1 + y2) / 2.0 - 4.0, gene.weight).unwrap();
+    }
+
+    for n in 0..genome.nodes {
+        let (x, y) = layout.positions[n];
+        writeln!(svg, "<circle class=\"node\" cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\"/>", x, y, NODE_RADIUS).unwrap();
+        writeln!(svg, "<text class=\"label\" x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+            x, y - NODE_RADIUS - 4.0, node_label(n, problem)).unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    return svg;
+}
+
+// writes a self-contained html page with the network diagram
+pub fn visualise<P: AsRef<Path>>(genome:&Genome, problem:&Problem, show_disabled:bool, path:P) -> std::io::Result<()> {
+    let mut html = String::from(HEADER);
+    html.push_str(&render_svg(genome, problem, show_disabled));
+    html.push_str(FOOTER);
+    return std::fs::write(path, html);
+}
+
+// node labels use dataset column names, first input node is the bias
+fn dot_label(n:usize, problem:&Problem, dataset:&Dataset) -> String {
+    let name = if n == 0 {
+        "bias".to_string()
+    } else if n < problem.inputs {
+        dataset.input_names.get(n - 1).cloned().unwrap_or_else(|| format!("in {}", n))
+    } else if n < problem.inputs + problem.outputs {
+        dataset.output_names.get(n - problem.inputs).cloned().unwrap_or_else(|| format!("out {}", n))
+    } else {
+        return format!("{}", n);
+    };
+    return name.replace('\\', "\\\\").replace('"', "\\\"");
+}
+
+// Graphviz graph with hidden nodes ranked by their depth 
+pub fn to_dot(genome:&Genome, problem:&Problem, dataset:&Dataset, show_disabled:bool) -> String {
+    let layout = layout(genome, problem);
+    let mut dot = String::new();
+    writeln!(dot, "digraph genome {{").unwrap();
+    writeln!(dot, "    rankdir=LR;").unwrap();
+    writeln!(dot, "    node [shape=circle];").unwrap();
+
+    for (column, layer) in layout.layers.iter().enumerate() {
+        let rank = if column == 0 {
+            "source"
+        } else if column == layout.layers.len() - 1 {
+            "sink"
+        } else {
+            "same"
+        };
+        writeln!(dot, "    {{").unwrap();
+        writeln!(dot, "        rank={};", rank).unwrap();
+        for &n in layer.iter() {
+            let shape = if n < problem.inputs + problem.outputs { "box" } else { "circle" };
+            writeln!(dot, "        {} [label=\"{}\", shape={}];", n, dot_label(n, problem, dataset), shape).unwrap();
+        }
+        writeln!(dot, "    }}").unwrap();
+    }
+
     for gene in genome.genes.iter() {
         if gene.enabled {
-          write!(&mut file, "
-          {{ data: 
-            {{ id: '{input}-{output}', source: '{input}', target: '{output}'}},
-            style: {{
-              'label': '{weight:.2}'
-            }} 
-          }},\n", input=gene.input, output=gene.output, weight=gene.weight).unwrap();
+            let color = if gene.weight >= 0f64 { POSITIVE_COLOR } else { NEGATIVE_COLOR };
+            writeln!(dot, "    {} -> {} [label=\"{:.2}\", color=\"{}\"];", gene.input, gene.output, gene.weight, color).unwrap();
+        } else if show_disabled {
+            writeln!(dot, "    {} -> {} [label=\"{:.2}\", style=dashed, color=\"#cccccc\"];", gene.input, gene.output, gene.weight).unwrap();
         }
     }
-    
-    file.write_all(FOOTER.as_bytes()).unwrap();
-}
\ No newline at end of file
+
+    writeln!(dot, "}}").unwrap();
+    return dot;
+}
+
+pub fn write_dot<P: AsRef<Path>>(genome:&Genome, problem:&Problem, dataset:&Dataset, show_disabled:bool, path:P) -> std::io::Result<()> {
+    return std::fs::write(path, to_dot(genome, problem, dataset, show_disabled));
+}
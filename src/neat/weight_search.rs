@@ -0,0 +1,348 @@
+use super::structs::genome::Genome;
+use super::structs::problem::Problem;
+use super::structs::config::Config;
+use super::generations::{calculate_base_fitness, Dataset};
+use super::genetics::standard_normal;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+// black-box optimisers of weights of a genome with fixed topology
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum WeightOptimiser {
+    // changes one weight at a time and keeps the change if fitness doesn't get worse
+    HillClimbing,
+    // changes all weights, step size is adapted with the 1/5 success rule
+    OnePlusOneEs,
+    // covariance matrix adaptation evolution strategy
+    CmaEs
+}
+
+impl fmt::Display for WeightOptimiser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", match self {
+            WeightOptimiser::HillClimbing => "hill",
+            WeightOptimiser::OnePlusOneEs => "es",
+            WeightOptimiser::CmaEs => "cma"
+        });
+    }
+}
+
+impl FromStr for WeightOptimiser {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        return match text {
+            "hill" => Ok(WeightOptimiser::HillClimbing),
+            "es" => Ok(WeightOptimiser::OnePlusOneEs),
+            "cma" => Ok(WeightOptimiser::CmaEs),
+            _ => Err(format!("unknown weight optimiser: {:?}", text))
+        };
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct WeightSearch {
+    pub optimiser: WeightOptimiser,
+    // number of evaluations of the genome on the training dataset
+    pub evaluations: usize,
+    // initial step size
+    pub sigma: f64
+}
+
+// enabled weights of a genome scored by its fitness function
+struct Objective<'a> {
+    genome: Genome,
+    genes: Vec<usize>,
+    problem: &'a Problem,
+    dataset: &'a Dataset,
+    min_weight: f64,
+    max_weight: f64,
+    evaluations: usize
+}
+
+impl Objective<'_> {
+    fn weights(&self) -> Vec<f64> {
+        return self.genes.iter().map(|&gene| self.genome.genes[gene].weight).collect();
+    }
+
+    fn set_weights(&mut self, weights:&[f64]) {
+        for (&gene, weight) in self.genes.iter().zip(weights) {
+            self.genome.genes[gene].weight = weight.clamp(self.min_weight, self.max_weight);
+        }
+    }
+
+    fn fitness(&mut self, weights:&[f64]) -> f64 {
+        self.set_weights(weights);
+        self.evaluations += 1;
+        return calculate_base_fitness(self.problem, &mut self.genome, self.dataset);
+    }
+}
+
+// searches weights of enabled genes with the best training fitness,
+// returned genome has the best weights found, which are never worse than the initial ones,
+// and it's returned with the number of evaluations used
+pub fn optimise<R: Rng + ?Sized>(rng: &mut R, genome:&Genome, problem:&Problem, config:&Config,
+    dataset:&Dataset, search:&WeightSearch) -> (Genome, usize) {
+
+    let mut objective = Objective {
+        genome: genome.clone(),
+        genes: (0..genome.genes.len()).filter(|&gene| genome.genes[gene].enabled).collect(),
+        problem,
+        dataset,
+        min_weight: config.min_weight,
+        max_weight: config.max_weight,
+        evaluations: 0
+    };
+    if objective.genes.is_empty() || search.evaluations == 0 {
+        return (objective.genome, 0);
+    }
+    let start = objective.weights();
+    let start_fitness = objective.fitness(&start);
+    let (best, _) = match search.optimiser {
+        WeightOptimiser::HillClimbing => hill_climbing(rng, &mut objective, start, start_fitness, search),
+        WeightOptimiser::OnePlusOneEs => one_plus_one_es(rng, &mut objective, start, start_fitness, search),
+        WeightOptimiser::CmaEs => cma_es(rng, &mut objective, start, start_fitness, search)
+    };
+    objective.set_weights(&best);
+    return (objective.genome, objective.evaluations);
+}
+
+fn hill_climbing<R: Rng + ?Sized>(rng: &mut R, objective:&mut Objective, mut best:Vec<f64>, mut best_fitness:f64,
+    search:&WeightSearch) -> (Vec<f64>, f64) {
+
+    while objective.evaluations < search.evaluations {
+        let mut candidate = best.clone();
+        let index = rng.gen_range(0..candidate.len());
+        candidate[index] += search.sigma * standard_normal(rng);
+        let fitness = objective.fitness(&candidate);
+        // equal fitness is accepted to move across plateaus
+        if fitness >= best_fitness {
+            best = candidate;
+            best_fitness = fitness;
+        }
+    }
+    return (best, best_fitness);
+}
+
+fn one_plus_one_es<R: Rng + ?Sized>(rng: &mut R, objective:&mut Objective, mut best:Vec<f64>, mut best_fitness:f64,
+    search:&WeightSearch) -> (Vec<f64>, f64) {
+
+    let mut sigma = search.sigma;
+    // damping of step size changes
+    let damping = 1f64 + best.len() as f64 / 2f64;
+    while objective.evaluations < search.evaluations {
+        let candidate:Vec<f64> = best.iter().map(|weight| weight + sigma * standard_normal(rng)).collect();
+        let fitness = objective.fitness(&candidate);
+        let success = fitness >= best_fitness;
+        if success {
+            best = candidate;
+            best_fitness = fitness;
+        }
+        // step grows if more than 1/5 of mutations are successful and shrinks otherwise
+        sigma *= ((if success { 1f64 } else { 0f64 } - 0.2) / (0.8 * damping)).exp();
+    }
+    return (best, best_fitness);
+}
+
+// eigenvalues and eigenvectors (columns) of a symmetric matrix computed with Jacobi rotations
+fn eigen(matrix:&[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut vectors:Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1f64 } else { 0f64 }).collect()).collect();
+    for _ in 0..100 {
+        let off_diagonal:f64 = (0..n).flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j]).sum();
+        if off_diagonal < 1e-20 {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].abs() < 1e-30 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2f64 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1f64).sqrt());
+                let c = 1f64 / (t * t + 1f64).sqrt();
+                let s = t * c;
+                // columns and then rows p and q are rotated
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                a[p] = row_p.iter().zip(&row_q).map(|(apk, aqk)| c * apk - s * aqk).collect();
+                a[q] = row_p.iter().zip(&row_q).map(|(apk, aqk)| s * apk + c * aqk).collect();
+                for row in vectors.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    return ((0..n).map(|i| a[i][i]).collect(), vectors);
+}
+
+// (mu/mu_w, lambda)-CMA-ES with default parameters from Hansen's tutorial
+fn cma_es<R: Rng + ?Sized>(rng: &mut R, objective:&mut Objective, start:Vec<f64>, start_fitness:f64,
+    search:&WeightSearch) -> (Vec<f64>, f64) {
+
+    let n = start.len();
+    let nf = n as f64;
+    let lambda = 4 + (3f64 * nf.ln()).floor() as usize;
+    let mu = lambda / 2;
+    let raw_weights:Vec<f64> = (0..mu).map(|i| (mu as f64 + 0.5).ln() - ((i + 1) as f64).ln()).collect();
+    let weights_sum:f64 = raw_weights.iter().sum();
+    let weights:Vec<f64> = raw_weights.iter().map(|w| w / weights_sum).collect();
+    let mueff = 1f64 / weights.iter().map(|w| w * w).sum::<f64>();
+
+    let cc = (4f64 + mueff / nf) / (nf + 4f64 + 2f64 * mueff / nf);
+    let cs = (mueff + 2f64) / (nf + mueff + 5f64);
+    let c1 = 2f64 / ((nf + 1.3).powi(2) + mueff);
+    let cmu = f64::min(1f64 - c1, 2f64 * (mueff - 2f64 + 1f64 / mueff) / ((nf + 2f64).powi(2) + mueff));
+    let damps = 1f64 + 2f64 * f64::max(0f64, ((mueff - 1f64) / (nf + 1f64)).sqrt() - 1f64) + cs;
+    let chi_n = nf.sqrt() * (1f64 - 1f64 / (4f64 * nf) + 1f64 / (21f64 * nf * nf));
+
+    let mut mean = start.clone();
+    let mut sigma = search.sigma;
+    let mut covariance:Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1f64 } else { 0f64 }).collect()).collect();
+    let mut pc = vec![0f64; n];
+    let mut ps = vec![0f64; n];
+    let mut best = start;
+    let mut best_fitness = start_fitness;
+    let mut generation = 0;
+
+    while objective.evaluations + lambda <= search.evaluations {
+        let (eigenvalues, basis) = eigen(&covariance);
+        let scales:Vec<f64> = eigenvalues.iter().map(|value| value.max(1e-20).sqrt()).collect();
+
+        // offspring as (fitness, step from the mean)
+        let mut offspring:Vec<(f64, Vec<f64>)> = Vec::new();
+        for _ in 0..lambda {
+            let z:Vec<f64> = (0..n).map(|i| scales[i] * standard_normal(rng)).collect();
+            let step:Vec<f64> = (0..n).map(|i| (0..n).map(|j| basis[i][j] * z[j]).sum()).collect();
+            let candidate:Vec<f64> = (0..n).map(|i| mean[i] + sigma * step[i]).collect();
+            let fitness = objective.fitness(&candidate);
+            if fitness > best_fitness {
+                best_fitness = fitness;
+                best = candidate;
+            }
+            offspring.push((fitness, step));
+        }
+        // fitness is maximised
+        offspring.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+        let mean_step:Vec<f64> = (0..n).map(|i| (0..mu).map(|k| weights[k] * offspring[k].1[i]).sum()).collect();
+        for i in 0..n {
+            mean[i] += sigma * mean_step[i];
+        }
+
+        // C^(-1/2) * mean step
+        let projected:Vec<f64> = (0..n).map(|j| (0..n).map(|i| basis[i][j] * mean_step[i]).sum::<f64>() / scales[j]).collect();
+        let whitened:Vec<f64> = (0..n).map(|i| (0..n).map(|j| basis[i][j] * projected[j]).sum()).collect();
+        for i in 0..n {
+            ps[i] = (1f64 - cs) * ps[i] + (cs * (2f64 - cs) * mueff).sqrt() * whitened[i];
+        }
+        let ps_norm = ps.iter().map(|x| x * x).sum::<f64>().sqrt();
+        generation += 1;
+        let hsig = ps_norm / (1f64 - (1f64 - cs).powi(2 * generation)).sqrt() / chi_n < 1.4 + 2f64 / (nf + 1f64);
+        let hsig = if hsig { 1f64 } else { 0f64 };
+        for i in 0..n {
+            pc[i] = (1f64 - cc) * pc[i] + hsig * (cc * (2f64 - cc) * mueff).sqrt() * mean_step[i];
+        }
+
+        for i in 0..n {
+            for j in 0..n {
+                let rank_mu:f64 = (0..mu).map(|k| weights[k] * offspring[k].1[i] * offspring[k].1[j]).sum();
+                covariance[i][j] = (1f64 - c1 - cmu) * covariance[i][j]
+                    + c1 * (pc[i] * pc[j] + (1f64 - hsig) * cc * (2f64 - cc) * covariance[i][j])
+                    + cmu * rank_mu;
+            }
+        }
+        sigma *= ((cs / damps) * (ps_norm / chi_n - 1f64)).exp();
+    }
+    return (best, best_fitness);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::evaluation::{FitnessFunction, OutputActivation};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn assert_close(actual:f64, expected:f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn eigen_decomposes_symmetric_matrix() {
+        let matrix = vec![vec![4.0, 1.0, -2.0], vec![1.0, 2.0, 0.5], vec![-2.0, 0.5, 3.0]];
+        let (values, vectors) = eigen(&matrix);
+        for k in 0..3 {
+            let vector:Vec<f64> = (0..3).map(|i| vectors[i][k]).collect();
+            for i in 0..3 {
+                let product:f64 = (0..3).map(|j| matrix[i][j] * vector[j]).sum();
+                assert_close(product, values[k] * vector[i]);
+            }
+            // eigenvectors are orthonormal columns
+            for l in 0..3 {
+                let dot:f64 = (0..3).map(|i| vectors[i][k] * vectors[i][l]).sum();
+                assert_close(dot, if k == l { 1f64 } else { 0f64 });
+            }
+        }
+        // trace is the sum of eigenvalues
+        assert_close(values.iter().sum(), 9f64);
+    }
+
+    fn problem() -> Problem {
+        return Problem {
+            inputs: 3,
+            outputs: 1,
+            output_activation: OutputActivation::Sigmoid,
+            fitness_function: FitnessFunction::MeanAbsoluteError
+        };
+    }
+
+    fn xor() -> Dataset {
+        return Dataset {
+            inputs: vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]],
+            outputs: vec![vec![0.0], vec![1.0], vec![1.0], vec![0.0]],
+            input_names: vec!["a".to_string(), "b".to_string()],
+            output_names: vec!["result".to_string()]
+        };
+    }
+
+    #[test]
+    fn optimisers_keep_budget_and_never_get_worse() {
+        let problem = problem();
+        let dataset = xor();
+        // hidden node 4 and a disabled gene which isn't optimised
+        let mut genome:Genome = "nodes 5\n0 3 0.1 true 0\n1 3 -0.2 true 0\n2 3 0.3 false 0\n1 4 0.4 true 1\n2 4 0.5 true 1\n4 3 -0.6 true 1\n"
+            .parse().unwrap();
+        let start_fitness = calculate_base_fitness(&problem, &mut genome, &dataset);
+        let config = Config { min_weight: -3.0, max_weight: 3.0, ..Config::default() };
+
+        for optimiser in [WeightOptimiser::HillClimbing, WeightOptimiser::OnePlusOneEs, WeightOptimiser::CmaEs] {
+            for budget in [0, 1, 7, 200] {
+                for seed in 0..5 {
+                    let search = WeightSearch { optimiser, evaluations: budget, sigma: 1.0 };
+                    let (mut optimised, evaluations) = optimise(&mut StdRng::seed_from_u64(seed), &genome, &problem, &config, &dataset, &search);
+                    assert!(evaluations <= budget, "{} used {} of {} evaluations", optimiser, evaluations, budget);
+                    let fitness = calculate_base_fitness(&problem, &mut optimised, &dataset);
+                    assert!(fitness >= start_fitness, "{} made fitness worse: {} < {}", optimiser, fitness, start_fitness);
+                    assert!(optimised.genes.iter().all(|gene| gene.weight.abs() <= 3.0));
+                    assert_eq!(optimised.genes[2].weight, 0.3);
+                    if budget == 200 {
+                        assert!(fitness > start_fitness, "{} didn't improve fitness {}", optimiser, start_fitness);
+                    }
+                }
+            }
+        }
+    }
+}
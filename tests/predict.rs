@@ -0,0 +1,46 @@
+#![allow(clippy::needless_return)]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+// directory of a single test, removed before it's used
+fn test_directory(name:&str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("neat_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    return directory;
+}
+
+// runs the predict command on xor inputs with a model whose bias is connected
+// to the only output with given weight, returns the predictions file
+fn predict(name:&str, model:&str) -> String {
+    let directory = test_directory(name);
+    std::fs::write(directory.join("inputs.csv"), "a,b\n0,0\n0,1\n1,0\n1,1\n").unwrap();
+    // outputs file gives only the names of output columns
+    std::fs::write(directory.join("outputs.csv"), "result\n").unwrap();
+    std::fs::write(directory.join("model.genome"), model).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_neat"))
+        .current_dir(&directory)
+        .args(["predict", "model.genome", "inputs.csv", "predictions.csv", "outputs.csv"])
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+    let predictions = std::fs::read_to_string(directory.join("predictions.csv")).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    return predictions;
+}
+
+#[test]
+fn predict_with_header_only_outputs() {
+    let predictions = predict("predict_sigmoid", "problem 3 1 sigmoid mae\nnodes 4\n0 3 0 true 0\n");
+    assert_eq!(predictions, "result\n0.5\n0.5\n0.5\n0.5\n");
+}
+
+#[test]
+fn predict_uses_activation_saved_with_the_model() {
+    // linear output is used without the --regression option
+    let predictions = predict("predict_identity", "problem 3 1 identity mae\nnodes 4\n0 3 2 true 0\n");
+    assert_eq!(predictions, "result\n2\n2\n2\n2\n");
+}
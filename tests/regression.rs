@@ -0,0 +1,63 @@
+#![allow(clippy::needless_return)]
+
+use neat::neat::evaluation::{FitnessFunction, OutputActivation};
+use neat::neat::generations::Dataset;
+use neat::neat::metrics::regression_report;
+use neat::neat::structs::genome::Genome;
+use neat::neat::structs::problem::Problem;
+
+fn sine_dataset() -> Dataset {
+    return Dataset::load("datasets/sine/inputs.csv", "datasets/sine/outputs.csv").unwrap();
+}
+
+// bias and x as inputs and a single linear output
+fn problem() -> Problem {
+    return Problem {
+        inputs: 2,
+        outputs: 1,
+        output_activation: OutputActivation::Identity,
+        fitness_function: FitnessFunction::MeanAbsoluteError
+    };
+}
+
+// the only gene connects bias to the output, so every prediction equals its weight
+fn constant_genome(value:f64) -> Genome {
+    return format!("nodes 3\n0 2 {} true 0\n", value).parse().unwrap();
+}
+
+fn assert_close(actual:f64, expected:f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+}
+
+#[test]
+fn constant_zero_prediction() {
+    let dataset = sine_dataset();
+    let targets:Vec<f64> = dataset.outputs.iter().map(|row| row[0]).collect();
+    let n = targets.len() as f64;
+    let mean = targets.iter().sum::<f64>() / n;
+    let total:f64 = targets.iter().map(|y| (y - mean).powi(2)).sum();
+    let squares:f64 = targets.iter().map(|y| y * y).sum();
+
+    let report = regression_report(&problem(), &mut constant_genome(0f64), &dataset);
+
+    assert_eq!(targets.len(), 61);
+    assert_close(report.mse, squares / n);
+    assert_close(report.rmse, (squares / n).sqrt());
+    assert_close(report.mae, targets.iter().map(|y| y.abs()).sum::<f64>() / n);
+    assert_close(report.r2, 1f64 - squares / total);
+    assert_close(report.max_error, targets.iter().fold(0f64, |max, y| max.max(y.abs())));
+}
+
+#[test]
+fn mean_prediction_explains_nothing() {
+    let dataset = sine_dataset();
+    let targets:Vec<f64> = dataset.outputs.iter().map(|row| row[0]).collect();
+    let n = targets.len() as f64;
+    let mean = targets.iter().sum::<f64>() / n;
+
+    let report = regression_report(&problem(), &mut constant_genome(mean), &dataset);
+
+    // squared error of the mean is the variance of targets
+    assert_close(report.mse, targets.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n);
+    assert_close(report.r2, 0f64);
+}